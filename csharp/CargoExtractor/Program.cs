@@ -75,7 +75,7 @@ class Program
         if (!File.Exists(manifestPath))
         {
             Console.WriteLine($"Error: No manifest.json in {outDir}");
-            Console.WriteLine("Run Rust extractor first: cargo run -- --config assets.json");
+            Console.WriteLine("Run Rust extractor first: cargo run -- batch --config assets.json");
             return;
         }
         
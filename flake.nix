@@ -40,7 +40,7 @@
             
             # Step 1: Extract from PAK using Rust
             echo "Step 1: Extracting assets from PAK..."
-            cargo run --release --quiet -- --config "$CONFIG"
+            cargo run --release --quiet -- batch --config "$CONFIG"
             
             # Step 2: Parse extracted assets using C#
             echo
@@ -98,8 +98,8 @@
             echo "Commands:"
             echo "  nix run .#extract        - Extract all assets from assets.json"
             echo "  nix run .#aggregate      - Aggregate JSON to SQLite database"
-            echo "  cargo run -- --list      - List available DataAssets"
-            echo "  cargo run -- --config X  - Extract assets from config file"
+            echo "  cargo run -- list        - List available DataAssets"
+            echo "  cargo run -- batch --config X - Extract assets from config file"
           '';
         };
         
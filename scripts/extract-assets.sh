@@ -13,7 +13,7 @@ echo
 
 # Step 1: Extract from PAK using Rust
 echo "Step 1: Extracting assets from PAK..."
-cargo run --release --quiet -- --config "$CONFIG"
+cargo run --release --quiet -- batch --config "$CONFIG"
 
 # Step 2: Parse extracted assets using C#
 echo
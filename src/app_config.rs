@@ -0,0 +1,73 @@
+//! `.mtpakrc`: TOML defaults for command-line flags, read from the current
+//! directory, or else the home directory. Flags given on the command line win.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+use serde::Deserialize;
+
+use crate::cli::{Cli, Command, KeyOrigin};
+
+const FILE_NAME: &str = ".mtpakrc";
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    /// PAK files to read, like repeated `--pak`
+    pub pak: Vec<PathBuf>,
+    /// Output directory for extract and batch, like `--out`; named like the user config's
+    pub out_dir: Option<PathBuf>,
+    /// File with the AES key, like `--keyfile`
+    pub keyfile: Option<PathBuf>,
+    /// Where to look for the AES key, like `--key-source`
+    pub key_source: Option<KeyOrigin>,
+}
+
+/// `.mtpakrc` in the current directory, then in the home directory
+pub fn config_path() -> Option<PathBuf> {
+    let local = PathBuf::from(FILE_NAME);
+    if local.is_file() {
+        return Some(local);
+    }
+    BaseDirs::new()
+        .map(|dirs| dirs.home_dir().join(FILE_NAME))
+        .filter(|path| path.is_file())
+}
+
+/// Load `.mtpakrc`, or defaults if there is none. Relative paths in it are
+/// relative to the file, so a `~/.mtpakrc` works from any directory.
+pub fn load() -> Result<AppConfig, Box<dyn std::error::Error>> {
+    let Some(path) = config_path() else {
+        return Ok(AppConfig::default());
+    };
+    
+    let content = fs::read_to_string(&path)?;
+    let mut config: AppConfig = toml::from_str(&content)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+    log::info!("Using {}", path.display());
+    
+    let base = path.parent().unwrap_or(Path::new(""));
+    for pak in &mut config.pak {
+        *pak = base.join(&*pak);
+    }
+    config.out_dir = config.out_dir.map(|out_dir| base.join(out_dir));
+    config.keyfile = config.keyfile.map(|keyfile| base.join(keyfile));
+    Ok(config)
+}
+
+impl AppConfig {
+    /// Fill in the flags the command line left unset
+    pub fn merge_into(self, cli: &mut Cli) {
+        if cli.pak.is_empty() {
+            cli.pak = self.pak;
+        }
+        cli.keyfile = cli.keyfile.take().or(self.keyfile);
+        cli.key_source = cli.key_source.or(self.key_source);
+        if let Some(Command::Extract { out, .. } | Command::Batch { out, .. }) = &mut cli.command
+            && out.is_none()
+        {
+            *out = self.out_dir;
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Asset paths as users type them and as the PAK stores them.
+
+use std::fmt;
+
+/// Extensions of the files that make up one asset, stripped by `normalize_asset_path`
+const ASSET_EXTENSIONS: [&str; 4] = [".uasset", ".uexp", ".ubulk", ".uptnl"];
+
+/// An asset path without extension and with forward slashes,
+/// e.g. `MotorTown/Content/DataAsset/Cargos`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssetPath(String);
+
+/// Normalize an asset path: backslashes become `/`, and one known extension
+/// (`.uasset`, `.uexp`, `.ubulk`, `.uptnl`, in any case) is stripped
+pub fn normalize_asset_path(path: &str) -> AssetPath {
+    let path = normalize_slashes(path.trim());
+    let stem_len = ASSET_EXTENSIONS.iter()
+        .find(|ext| path.len() >= ext.len() && path.as_bytes()[path.len() - ext.len()..].eq_ignore_ascii_case(ext.as_bytes()))
+        .map_or(path.len(), |ext| path.len() - ext.len());
+    AssetPath(path[..stem_len].to_string())
+}
+
+/// A path with Windows backslashes turned into the forward slashes PAKs use
+pub fn normalize_slashes(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+impl AssetPath {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    
+    /// Last path component, e.g. `Cargos`
+    pub fn file_name(&self) -> &str {
+        self.0.rsplit('/').next().unwrap_or(&self.0)
+    }
+    
+    /// The path with `ext` (including the dot) appended
+    pub fn with_extension(&self, ext: &str) -> String {
+        format!("{}{}", self.0, ext)
+    }
+    
+    pub fn uasset(&self) -> String {
+        self.with_extension(".uasset")
+    }
+    
+    pub fn uexp(&self) -> String {
+        self.with_extension(".uexp")
+    }
+}
+
+impl fmt::Display for AssetPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn strips_one_known_extension() {
+        let cargos = normalize_asset_path("MotorTown/Content/DataAsset/Cargos.uasset");
+        assert_eq!(cargos.as_str(), "MotorTown/Content/DataAsset/Cargos");
+        assert_eq!(cargos.uasset(), "MotorTown/Content/DataAsset/Cargos.uasset");
+        assert_eq!(cargos.uexp(), "MotorTown/Content/DataAsset/Cargos.uexp");
+        assert_eq!(cargos.file_name(), "Cargos");
+        
+        assert_eq!(normalize_asset_path("Dir/Cargos.uexp").as_str(), "Dir/Cargos");
+        assert_eq!(normalize_asset_path("Dir/Cargos.UASSET").as_str(), "Dir/Cargos");
+        assert_eq!(normalize_asset_path("Dir/Cargos").as_str(), "Dir/Cargos");
+        assert_eq!(normalize_asset_path("Dir/Cargos.json").as_str(), "Dir/Cargos.json");
+    }
+    
+    #[test]
+    fn double_extensions_lose_only_the_last_one() {
+        assert_eq!(normalize_asset_path("Dir/Cargos.uasset.uasset").as_str(), "Dir/Cargos.uasset");
+        assert_eq!(normalize_asset_path("Dir/Cargos.uexp.uasset").as_str(), "Dir/Cargos.uexp");
+        assert_eq!(normalize_asset_path("My.uasset.backup/File.uasset").as_str(), "My.uasset.backup/File");
+    }
+    
+    #[test]
+    fn backslashes_become_forward_slashes() {
+        let cargos = normalize_asset_path(r"MotorTown\Content\DataAsset\Cargos.uasset");
+        assert_eq!(cargos.as_str(), "MotorTown/Content/DataAsset/Cargos");
+        assert_eq!(cargos.file_name(), "Cargos");
+        assert_eq!(normalize_asset_path(r"MotorTown\Content/DataAsset\Cargos").uexp(), "MotorTown/Content/DataAsset/Cargos.uexp");
+    }
+}
@@ -0,0 +1,104 @@
+//! `--backup`: before an extracted file overwrites one with different content, the
+//! old file is kept as `<name>.bak`, so edits made in the output directory survive
+//! re-extracting. Files that would be rewritten unchanged are left alone.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Call before writing `data` to `path`: moves an existing file that differs from
+/// `data` to its backup name
+pub fn before_write(path: &Path, data: &[u8]) -> io::Result<()> {
+    if !ENABLED.load(Ordering::Relaxed) || !path.is_file() {
+        return Ok(());
+    }
+    if fs::metadata(path)?.len() == data.len() as u64 && fs::read(path)? == data {
+        return Ok(());
+    }
+    keep(path)?;
+    Ok(())
+}
+
+/// For files written in pieces, whose content isn't known up front: moves an
+/// existing file to its backup name. Pass the result to `after_write`.
+pub fn before_stream(path: &Path) -> io::Result<Option<PathBuf>> {
+    if !ENABLED.load(Ordering::Relaxed) || !path.is_file() {
+        return Ok(None);
+    }
+    keep(path).map(Some)
+}
+
+/// Drops the backup made by `before_stream` if the new file turned out the same
+pub fn after_write(path: &Path, backup: Option<PathBuf>) -> io::Result<()> {
+    if let Some(backup) = backup
+        && same_contents(path, &backup)?
+    {
+        fs::remove_file(&backup)?;
+    }
+    Ok(())
+}
+
+/// Rename `path` to `<name>.bak`, or `<name>.bak.1`, `<name>.bak.2`, ... if older backups exist
+fn keep(path: &Path) -> io::Result<PathBuf> {
+    let backup = backup_path(path, |candidate| candidate.exists());
+    fs::rename(path, &backup)?;
+    log::info!("Kept the previous {} as {}", path.display(), backup.display());
+    Ok(backup)
+}
+
+fn backup_path(path: &Path, exists: impl Fn(&Path) -> bool) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    let first = PathBuf::from(name);
+    if !exists(&first) {
+        return first;
+    }
+    (1..)
+        .map(|n| {
+            let mut name = first.as_os_str().to_owned();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        })
+        .find(|candidate| !exists(candidate))
+        .expect("some backup name is free")
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    let mut a = BufReader::new(File::open(a)?);
+    let mut b = BufReader::new(File::open(b)?);
+    let mut buf_a = [0; 64 * 1024];
+    let mut buf_b = [0; 64 * 1024];
+    loop {
+        let read = a.read(&mut buf_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn backup_path_skips_existing_backups() {
+        let path = Path::new("out/Cargos.uasset");
+        assert_eq!(backup_path(path, |_| false), Path::new("out/Cargos.uasset.bak"));
+        let taken = [Path::new("out/Cargos.uasset.bak"), Path::new("out/Cargos.uasset.bak.1")];
+        assert_eq!(backup_path(path, |p| taken.contains(&p)), Path::new("out/Cargos.uasset.bak.2"));
+    }
+}
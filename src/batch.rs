@@ -0,0 +1,380 @@
+//! Parallel batch extraction for `--config` mode.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+
+use rayon::ThreadPoolBuilder;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_path::{normalize_asset_path, AssetPath};
+use crate::container::Container;
+use crate::error::ExtractError;
+use crate::index::{self, PakIndex};
+use crate::progress::Progress;
+use crate::{CollisionRecord, ExtractedAsset, VerificationFailure};
+use crate::say;
+
+/// What to do when two assets would be written to the same flat file name
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Abort before extracting anything
+    Error,
+    /// Write later assets as `<name>_2`, `<name>_3`, ...
+    Rename,
+    /// Keep the first asset and don't extract the others
+    Skip,
+}
+
+pub struct BatchOptions<'a> {
+    pub pak_path: &'a Path,
+    pub out_dir: &'a Path,
+    /// Only extract assets whose .uasset has this encryption flag
+    pub encryption_filter: Option<bool>,
+    /// Check each extracted entry against the hash stored in the PAK
+    pub verify: bool,
+    /// Required when filtering or verifying; also records stored sizes in the manifest
+    pub index: Option<&'a PakIndex>,
+    /// Extracted files waiting for the writer thread before workers block
+    pub write_queue: usize,
+    /// Recreate the PAK's directory tree under `out_dir` instead of writing flat
+    pub preserve_paths: bool,
+    /// Leave each asset's `.uexp` in the PAK
+    pub exclude_uexp: bool,
+    /// Flat output names from `resolve_flat_names`, where they differ from the file name
+    pub flat_names: Option<&'a HashMap<String, String>>,
+    /// Extraction worker threads (0 = one per CPU)
+    pub jobs: usize,
+    /// Advanced once per asset with the bytes it extracted; per-asset lines are printed around it
+    pub progress: &'a Progress,
+}
+
+/// A file for the writer thread to put on disk
+type WriteJob = (PathBuf, Vec<u8>);
+
+pub enum Outcome {
+    Extracted(Box<ExtractedAsset>, Vec<VerificationFailure>),
+    Skipped,
+    Failed(ExtractError),
+}
+
+/// Extract every asset in parallel, each worker with its own PAK file handle.
+/// Workers hand files to a single writer thread through a bounded queue, so
+/// decryption keeps going while slow storage catches up, and memory stays capped.
+/// Results are returned in the same order as `assets`.
+pub fn extract_assets(pak: &dyn Container, assets: &[String], options: &BatchOptions) -> io::Result<Vec<(String, Outcome)>> {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(options.jobs)
+        .build()
+        .map_err(io::Error::other)?;
+    let (tx, rx) = mpsc::sync_channel::<WriteJob>(options.write_queue);
+    
+    thread::scope(|scope| {
+        let writer = scope.spawn(move || -> io::Result<()> {
+            for (path, data) in rx {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                crate::backup::before_write(&path, &data)?;
+                fs::write(&path, &data)?;
+            }
+            Ok(())
+        });
+        
+        let results = pool.install(|| {
+            assets.par_iter()
+                .map_init(
+                    || File::open(options.pak_path).map(BufReader::new),
+                    |reader, asset_path| {
+                        let reader = reader.as_mut().map_err(|e| io::Error::new(e.kind(), e.to_string()))?;
+                        let asset_path = normalize_asset_path(asset_path);
+                        let outcome = extract_asset(pak, reader, &asset_path, options, &tx)?;
+                        let bytes = match &outcome {
+                            Outcome::Extracted(asset, _) => asset.uasset_size + asset.uexp_size.unwrap_or(0),
+                            _ => 0,
+                        };
+                        options.progress.inc(bytes);
+                        Ok((asset_path.to_string(), outcome))
+                    },
+                )
+                .collect()
+        });
+        
+        // Close the queue so the writer finishes; its error explains any failed sends
+        drop(tx);
+        writer.join().expect("writer thread panicked")?;
+        results
+    })
+}
+
+fn queue_write(tx: &SyncSender<WriteJob>, path: PathBuf, data: Vec<u8>) -> io::Result<()> {
+    tx.send((path, data))
+        .map_err(|_| io::Error::other("writer thread stopped"))
+}
+
+fn extract_asset(
+    pak: &dyn Container,
+    reader: &mut BufReader<File>,
+    asset: &AssetPath,
+    options: &BatchOptions,
+    tx: &SyncSender<WriteJob>,
+) -> io::Result<Outcome> {
+    let asset_path = asset.as_str();
+    let name = asset.file_name();
+    
+    let uasset_pak_path = asset.uasset();
+    let uexp_pak_path = asset.uexp();
+    
+    let out_name = if options.preserve_paths {
+        if let Err(e) = crate::check_entry_path(asset_path) {
+            options.progress.suspend(|| log::error!("{}: {}", name, e));
+            return Ok(Outcome::Failed(e));
+        }
+        asset_path
+    } else {
+        flat_name(options.flat_names, asset_path, name)
+    };
+    
+    if let (Some(want), Some(pak_index)) = (options.encryption_filter, options.index)
+        && let Some(entry) = pak_index.find(&uasset_pak_path)
+        && entry.encrypted != want
+    {
+        options.progress.suspend(|| say!("  {} ... skipped ({})", name, if entry.encrypted { "encrypted" } else { "plaintext" }));
+        return Ok(Outcome::Skipped);
+    }
+    
+    let uasset_data = match pak.get(&uasset_pak_path, reader) {
+        Ok(data) => data,
+        Err(e) => {
+            // With the index at hand, tell a missing asset apart from one that failed to read
+            let entry = options.index.and_then(|pak_index| pak_index.find(&uasset_pak_path));
+            let e = if options.index.is_some() && entry.is_none() {
+                ExtractError::AssetNotFound(uasset_pak_path.clone())
+            } else {
+                e.with_entry(entry)
+            };
+            options.progress.suspend(|| log::error!("{}: {}", name, e));
+            return Ok(Outcome::Failed(e));
+        }
+    };
+    let uasset_size = uasset_data.len();
+    let uasset_sha256 = crate::sha256_hex(&uasset_data);
+    queue_write(tx, options.out_dir.join(format!("{}.uasset", out_name)), uasset_data)?;
+    
+    let uexp_result = if options.exclude_uexp { None } else { pak.get(&uexp_pak_path, reader).ok() };
+    let (uexp_out, uexp_size, uexp_sha256) = match uexp_result {
+        Some(uexp_data) => {
+            let uexp_size = uexp_data.len() as u64;
+            let uexp_sha256 = crate::sha256_hex(&uexp_data);
+            queue_write(tx, options.out_dir.join(format!("{}.uexp", out_name)), uexp_data)?;
+            (Some(format!("{}.uexp", out_name)), Some(uexp_size), Some(uexp_sha256))
+        }
+        None => (None, None, None),
+    };
+    
+    let uasset_entry = options.index.and_then(|pak_index| pak_index.find(&uasset_pak_path));
+    let uexp_entry = options.index.filter(|_| uexp_out.is_some()).and_then(|pak_index| pak_index.find(&uexp_pak_path));
+    let compressed_size = uasset_entry.map(|e| e.stored_size() + uexp_entry.map_or(0, |e| e.stored_size()));
+    
+    let mut failures = Vec::new();
+    let mut verified = None;
+    if options.verify && let Some(pak_index) = options.index {
+        let mut entries = vec![uasset_pak_path.as_str()];
+        if uexp_out.is_some() {
+            entries.push(&uexp_pak_path);
+        }
+        for entry_path in entries {
+            let Some(entry) = pak_index.find(entry_path) else { continue };
+            let Some(expected) = index::stored_hash(reader, pak_index.version, entry)? else { continue };
+            let actual = index::data_hash(reader, entry)?;
+            if actual != expected {
+                failures.push(VerificationFailure {
+                    entry: entry_path.to_string(),
+                    expected: hex::encode(expected),
+                    actual: hex::encode(actual),
+                });
+            }
+            verified = Some(failures.is_empty());
+        }
+    }
+    
+    options.progress.suspend(|| match verified {
+        Some(true) => say!("  {} ... OK ({} bytes, hash verified)", name, uasset_size),
+        Some(false) => say!("  {} ... OK ({} bytes), HASH MISMATCH", name, uasset_size),
+        None => say!("  {} ... OK ({} bytes)", name, uasset_size),
+    });
+    
+    let asset = ExtractedAsset {
+        name: name.to_string(),
+        pak_path: asset_path.to_string(),
+        uasset: format!("{}.uasset", out_name),
+        uexp_entry: uexp_out.as_ref().map(|_| uexp_pak_path),
+        uexp: uexp_out,
+        uasset_entry: uasset_pak_path,
+        uasset_size: uasset_size as u64,
+        uexp_size,
+        compressed_size,
+        compression: uasset_entry.map(|e| e.compression_name().to_string()),
+        sha256: uasset_sha256,
+        uexp_sha256,
+        verified,
+    };
+    Ok(Outcome::Extracted(Box::new(asset), failures))
+}
+
+/// Print the files `extract_assets` would write for each asset, checking that the
+/// asset exists in the PAK, without touching the disk. Returns the number of assets
+/// that would be extracted.
+pub fn print_plan(
+    pak_index: &PakIndex,
+    assets: &[String],
+    out_dir: &Path,
+    preserve_paths: bool,
+    exclude_uexp: bool,
+    flat_names: Option<&HashMap<String, String>>,
+    encryption_filter: Option<bool>,
+) -> usize {
+    let mut planned = 0;
+    for asset_path in assets {
+        let asset = normalize_asset_path(asset_path);
+        let asset_path = asset.as_str();
+        let name = asset.file_name();
+        let out_name = if preserve_paths {
+            if let Err(e) = crate::check_entry_path(asset_path) {
+                say!("  {} ... would fail: {}", asset_path, e);
+                continue;
+            }
+            asset_path
+        } else {
+            flat_name(flat_names, asset_path, name)
+        };
+        
+        let Some(uasset_entry) = pak_index.find(&asset.uasset()) else {
+            say!("  {} ... NOT FOUND in PAK", asset_path);
+            continue;
+        };
+        if let Some(want) = encryption_filter && uasset_entry.encrypted != want {
+            say!("  {} ... would be skipped ({})", asset_path, if uasset_entry.encrypted { "encrypted" } else { "plaintext" });
+            continue;
+        }
+        
+        say!("  {} -> {}", asset_path, out_dir.join(format!("{}.uasset", out_name)).display());
+        if !exclude_uexp && pak_index.find(&asset.uexp()).is_some() {
+            say!("    + {}", out_dir.join(format!("{}.uexp", out_name)).display());
+        }
+        planned += 1;
+    }
+    planned
+}
+
+/// Flat output name of an asset: its renamed name if it collided, else its file name
+fn flat_name<'a>(flat_names: Option<&'a HashMap<String, String>>, asset_path: &str, name: &'a str) -> &'a str {
+    flat_names.and_then(|names| names.get(asset_path)).map_or(name, String::as_str)
+}
+
+/// How assets that share a file name are written flat
+pub struct FlatNames {
+    /// Output name (without extension) of each renamed asset, keyed by asset path
+    pub renamed: HashMap<String, String>,
+    /// Assets to leave out
+    pub skipped: Vec<String>,
+    pub collisions: Vec<CollisionRecord>,
+}
+
+/// Resolve assets that share a file name when written flat. Names are compared
+/// case-insensitively, as on Windows. With `CollisionPolicy::Error`, any collision
+/// is an error listing all of them.
+pub fn resolve_flat_names(assets: &[String], policy: CollisionPolicy) -> Result<FlatNames, String> {
+    let file_name = |asset_path: &str| {
+        Path::new(asset_path).file_name().and_then(|s| s.to_str()).unwrap_or("asset").to_string()
+    };
+    
+    // Every natural name goes to the first asset that has it, so a renamed
+    // `Cargos_2` can't take the name of a later asset that is really called that
+    let mut taken: HashMap<String, String> = HashMap::new();
+    let mut collided = Vec::new();
+    for asset in assets {
+        let asset = normalize_asset_path(asset);
+        let asset_path = asset.as_str();
+        let name = file_name(asset_path);
+        match taken.get(&name.to_lowercase()) {
+            Some(first) => collided.push((asset_path.to_string(), first.clone(), name)),
+            None => {
+                taken.insert(name.to_lowercase(), asset_path.to_string());
+            }
+        }
+    }
+    
+    if policy == CollisionPolicy::Error && !collided.is_empty() {
+        let list: Vec<String> = collided.iter()
+            .map(|(asset_path, first, name)| format!("  {}: {} and {}", name, first, asset_path))
+            .collect();
+        return Err(format!(
+            "{} assets share a file name with another asset (use --preserve-paths or --on-collision rename/skip):\n{}",
+            collided.len(),
+            list.join("\n"),
+        ));
+    }
+    
+    let mut renamed = HashMap::new();
+    let mut skipped = Vec::new();
+    let mut records = Vec::new();
+    for (asset_path, first, name) in collided {
+        let written_as = match policy {
+            CollisionPolicy::Rename => {
+                let new_name = (2..)
+                    .map(|n| format!("{}_{}", name, n))
+                    .find(|candidate| !taken.contains_key(&candidate.to_lowercase()))
+                    .expect("unbounded suffixes");
+                taken.insert(new_name.to_lowercase(), asset_path.clone());
+                renamed.insert(asset_path.clone(), new_name.clone());
+                Some(format!("{}.uasset", new_name))
+            }
+            CollisionPolicy::Skip => {
+                skipped.push(asset_path.clone());
+                None
+            }
+            CollisionPolicy::Error => unreachable!("collisions are an error above"),
+        };
+        records.push(CollisionRecord { asset: asset_path, conflicts_with: first, strategy: policy, written_as });
+    }
+    Ok(FlatNames { renamed, skipped, collisions: records })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use repak::Version;
+    
+    fn entry(path: &str) -> index::EntryInfo {
+        index::EntryInfo {
+            path: path.to_string(),
+            offset: 0,
+            data_offset: 0,
+            compressed_size: 0,
+            uncompressed_size: 0,
+            compression: None,
+            encrypted: false,
+            blocks: Vec::new(),
+        }
+    }
+    
+    #[test]
+    fn config_assets_lose_only_one_extension() {
+        // A real asset whose name ends in a recognized extension, next to its .uexp
+        let pak_index = PakIndex {
+            version: Version::V11,
+            entries: vec![entry("Dir/Cargos.uexp.uasset"), entry("Dir/Cargos.uexp.uexp")],
+        };
+        let out_dir = Path::new("out");
+        
+        assert_eq!(print_plan(&pak_index, &["Dir/Cargos.uexp.uasset".to_string()], out_dir, true, false, None, None), 1);
+        // Chained trimming turned the above into this, which isn't in the PAK
+        assert_eq!(print_plan(&pak_index, &["Dir/Cargos".to_string()], out_dir, true, false, None, None), 0);
+    }
+}
@@ -0,0 +1,67 @@
+//! Hidden `bench-entry` command: time where extracting one entry spends its time.
+//!
+//! Reading and decryption are timed on our own index reader. repak doesn't
+//! expose its phases, so decompression is what's left of a full `pak.get`
+//! once the read and decrypt times are taken off.
+
+use std::io::{Read, Seek};
+use std::time::{Duration, Instant};
+
+use aes::Aes256;
+
+use crate::container::Container;
+use crate::error::ExtractError;
+use crate::index;
+use crate::say;
+
+pub fn bench_entry<R: Read + Seek>(
+    file: &mut R,
+    pak: &dyn Container,
+    key: &Aes256,
+    path: &str,
+    iterations: u32,
+) -> Result<(), ExtractError> {
+    let pak_index = index::read_index(file, Some(key))?;
+    let entry = pak_index.find(path)
+        .ok_or_else(|| ExtractError::AssetNotFound(path.to_string()))?;
+    let read_error = |e: ExtractError| e.with_entry(Some(entry));
+    
+    say!("=== bench-entry: {} ===", path);
+    say!("  compression: {}, encrypted: {}, {} block(s)",
+        entry.compression_name(), entry.encrypted, entry.blocks.len().max(1));
+    say!("  stored: {} bytes, uncompressed: {} bytes", entry.stored_size(), entry.uncompressed_size);
+    
+    // Warm the OS cache so the first iteration doesn't skew the averages
+    pak.get(path, file).map_err(read_error)?;
+    
+    let mut read = Duration::ZERO;
+    let mut decrypt = Duration::ZERO;
+    let mut total = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let mut blocks = index::read_stored_blocks(file, entry)?;
+        read += start.elapsed();
+        
+        if entry.encrypted {
+            let start = Instant::now();
+            index::decrypt_blocks(key, entry, &mut blocks);
+            decrypt += start.elapsed();
+        }
+        
+        let start = Instant::now();
+        pak.get(path, file).map_err(read_error)?;
+        total += start.elapsed();
+    }
+    
+    let decompress = total.saturating_sub(read + decrypt);
+    let average = |d: Duration| d / iterations;
+    let share = |d: Duration| 100.0 * d.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON);
+    
+    say!("\n=== Average over {} iterations ===", iterations);
+    say!("  read:       {:>10.3?} ({:5.1}%)", average(read), share(read));
+    say!("  decrypt:    {:>10.3?} ({:5.1}%)", average(decrypt), share(decrypt));
+    say!("  decompress: {:>10.3?} ({:5.1}%)", average(decompress), share(decompress));
+    say!("  total:      {:>10.3?}", average(total));
+    
+    Ok(())
+}
@@ -0,0 +1,961 @@
+//! Pack modified .uasset files (with their .uexp/.ubulk/.uptnl) into a PAK that MotorTown loads as a mod.
+//!
+//! Run with --help for the options.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use aes::Aes256;
+use clap::Parser;
+use log::{error, info, warn, LevelFilter};
+use notify::{RecursiveMode, Watcher};
+use repak::{Compression, PakBuilder, PakReader, Version};
+use serde::{Deserialize, Serialize};
+
+use mt_pak_extract::asset_path::{normalize_asset_path, normalize_slashes};
+use mt_pak_extract::signature::Signature;
+use mt_pak_extract::{error, index, suggest, user_config, Manifest};
+
+const DEFAULT_MOUNT_POINT: &str = "../../../";
+
+/// Quiet time after a change before `--watch` rebuilds
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Files next to a .uasset that belong to the same asset and must be packed with it
+const SIBLING_EXTENSIONS: [&str; 3] = [".uexp", ".ubulk", ".uptnl"];
+
+/// Send files whose name starts with `prefix` to `folder` inside the PAK
+#[derive(Deserialize)]
+struct PathRule {
+    prefix: String,
+    folder: String,
+}
+
+/// Rules are tried in order; files matching none go to `default_folder`
+#[derive(Deserialize)]
+struct Mapping {
+    #[serde(default = "default_folder")]
+    default_folder: String,
+    rules: Vec<PathRule>,
+}
+
+/// A local .uasset and the internal path it is packed to. Its siblings go next to it.
+struct Input {
+    local: String,
+    pak_path: String,
+}
+
+/// One file going into the PAK: an input or one of its siblings
+struct PackedInput {
+    local: String,
+    pak_path: String,
+    sibling: bool,
+}
+
+/// Bumped when `BuildState` changes, which makes the next --only-changed build a full one
+const STATE_VERSION: u32 = 1;
+
+/// `<output>.state.json`, written by --only-changed builds: what the next one compares against
+#[derive(Default, Serialize, Deserialize)]
+struct BuildState {
+    version: u32,
+    /// Everything besides the inputs that shapes the PAK; if it changes, everything is repacked
+    settings: String,
+    /// Size and mtime of the PAK this build wrote, to notice it being replaced
+    pak_size: u64,
+    pak_modified: Option<SystemTime>,
+    /// Local file -> what was packed from it
+    files: BTreeMap<String, PackedFile>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct PackedFile {
+    pak_path: String,
+    size: u64,
+    modified: SystemTime,
+    sha256: String,
+}
+
+fn state_path(output: &str) -> String {
+    format!("{}.state.json", output)
+}
+
+/// The state of the last build, if `output` is still the PAK it wrote with the same settings
+fn previous_build(output: &str, settings: &str) -> Option<BuildState> {
+    let full_rebuild = |reason: &str| {
+        info!("Packing every file: {}", reason);
+        None
+    };
+    let Ok(content) = fs::read_to_string(state_path(output)) else {
+        return full_rebuild("no previous build state");
+    };
+    let state: BuildState = match serde_json::from_str(&content) {
+        Ok(state) => state,
+        Err(e) => return full_rebuild(&format!("unreadable build state ({})", e)),
+    };
+    if state.version != STATE_VERSION {
+        return full_rebuild("build state from another version");
+    }
+    if state.settings != settings {
+        return full_rebuild("the PAK settings or mapping changed");
+    }
+    match fs::metadata(output) {
+        Ok(metadata) if metadata.len() == state.pak_size && metadata.modified().ok() == state.pak_modified => Some(state),
+        Ok(_) => full_rebuild(&format!("{} changed since the last build", output)),
+        Err(_) => full_rebuild(&format!("{} is missing", output)),
+    }
+}
+
+/// What `file` is now, and whether it is the same as `record` from the last build.
+/// A file whose size and mtime match is taken as unchanged without reading it.
+fn packed_file(file: &PackedInput, record: Option<&PackedFile>) -> io::Result<(PackedFile, bool)> {
+    let metadata = fs::metadata(&file.local)?;
+    let modified = metadata.modified()?;
+    if let Some(record) = record
+        && record.size == metadata.len()
+        && record.modified == modified
+    {
+        return Ok((record.clone(), true));
+    }
+    let sha256 = mt_pak_extract::sha256_hex(&fs::read(&file.local)?);
+    let same = record.is_some_and(|record| record.sha256 == sha256);
+    Ok((PackedFile { pak_path: file.pak_path.clone(), size: metadata.len(), modified, sha256 }, same))
+}
+
+fn default_folder() -> String {
+    "MotorTown/Content/DataAsset".to_string()
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        let delivery_point = "MotorTown/Content/Objects/Mission/Delivery/DeliveryPoint";
+        let rule = |prefix: &str, folder: &str| PathRule {
+            prefix: prefix.to_string(),
+            folder: folder.to_string(),
+        };
+        Mapping {
+            default_folder: default_folder(),
+            rules: vec![
+                rule("Factory_", delivery_point),
+                rule("Farm_", delivery_point),
+                rule("Mine_", delivery_point),
+                rule("SteelMill_", delivery_point),
+                rule("Storage_", delivery_point),
+                rule("Store_", delivery_point),
+                rule("Cargos.uasset", "MotorTown/Content/DataAsset"),
+                rule("Vehicles", "MotorTown/Content/DataAsset/Vehicles"),
+            ],
+        }
+    }
+}
+
+/// Pack modified .uasset files (with their .uexp/.ubulk/.uptnl) into a PAK that MotorTown loads as a mod
+#[derive(Parser)]
+#[command(name = "repack")]
+struct Args {
+    /// PAK file to write
+    output: String,
+    
+    /// .uasset files to pack; the .uexp/.ubulk/.uptnl next to each are packed with it
+    #[arg(required_unless_present = "from_manifest")]
+    files: Vec<String>,
+    
+    /// Pack the assets listed in a batch manifest, read from the manifest's directory and
+    /// packed to the PAK paths they were extracted from
+    #[arg(long, value_name = "MANIFEST", conflicts_with_all = ["files", "mapping", "input_root"])]
+    from_manifest: Option<String>,
+    
+    /// JSON or TOML rules mapping file name prefixes to PAK folders (default: the built-in
+    /// MotorTown rules)
+    #[arg(long, conflicts_with = "input_root")]
+    mapping: Option<String>,
+    
+    /// Take each input's PAK path from where it sits under this directory, laid out like
+    /// `extract --out-layout mount` (the mount point's folder, then the internal path)
+    #[arg(long)]
+    input_root: Option<String>,
+    
+    /// Write an encrypted index using the AES key (default: unencrypted)
+    #[arg(long)]
+    encrypt: bool,
+    
+    /// AES key as hex for --encrypt and --validate-against, instead of the KEY environment
+    /// variable (overrides --keyfile)
+    #[arg(long)]
+    key: Option<String>,
+    
+    /// File with the AES key, as raw hex or {"key": "0x..."}
+    #[arg(long)]
+    keyfile: Option<String>,
+    
+    /// Look for the key in the OS credential store before KEY with `keyring`
+    #[arg(long, value_parser = ["env", "keyring"])]
+    key_source: Option<String>,
+    
+    /// PAK version to write, e.g. V8A, V9, V10 (V11 is MotorTown's)
+    #[arg(long, value_parser = mt_pak_extract::parse_version, default_value = "V11")]
+    version: Version,
+    
+    /// none, zlib, gzip, zstd, lz4 or oodle
+    #[arg(long, default_value = "zlib")]
+    compression: String,
+    
+    /// <ext>=<method>: compression for files with this extension instead, e.g. .ubulk=none
+    /// (repeatable; a PAK holds one method, so the others must be none or match)
+    #[arg(long, value_name = "EXT=METHOD", value_parser = parse_compression_for)]
+    compression_for: Vec<(String, Option<Compression>)>,
+    
+    /// Directory prefix the entries are mounted under
+    #[arg(long, default_value = DEFAULT_MOUNT_POINT)]
+    mount_point: String,
+    
+    /// Seed of the V10+ path hash index, decimal or 0x-prefixed hex (default: 0)
+    #[arg(long, value_parser = parse_seed)]
+    path_hash_seed: Option<u64>,
+    
+    /// Also write <output>.sig with the PAK's chunk hashes (the RSA part is left empty)
+    #[arg(long)]
+    sign: bool,
+    
+    /// Compare the inputs with <output>.state.json from the last build, copy the unchanged
+    /// ones from the previous PAK, and don't rebuild at all if nothing changed
+    #[arg(long)]
+    only_changed: bool,
+    
+    /// Warn about inputs whose PAK path is not in this game PAK, so they wouldn't override anything
+    #[arg(long, value_name = "BASE_PAK")]
+    validate_against: Option<PathBuf>,
+    
+    /// Check the inputs and print where each file would be packed, without writing the PAK
+    #[arg(long, conflicts_with = "watch")]
+    dry_run: bool,
+    
+    /// Skip missing inputs instead of failing (exits 4 otherwise, before writing anything)
+    #[arg(long)]
+    ignore_errors: bool,
+    
+    /// After packing, rebuild the PAK whenever an input or its siblings change, until Ctrl-C
+    #[arg(long)]
+    watch: bool,
+}
+
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            // Usage errors exit 1 like other unexpected errors (2 means asset not found)
+            let _ = e.print();
+            return if e.use_stderr() { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+        }
+    };
+    
+    mt_pak_extract::init_logging(LevelFilter::Info);
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::from(error::exit_code(e.as_ref()))
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    println!("=== MotorTown PAK Repacker ===");
+    
+    let Args {
+        output,
+        files,
+        from_manifest: manifest_path,
+        mapping: mapping_path,
+        input_root,
+        encrypt,
+        key,
+        keyfile,
+        key_source,
+        version,
+        compression,
+        compression_for,
+        mut mount_point,
+        path_hash_seed,
+        sign,
+        only_changed,
+        validate_against,
+        dry_run,
+        ignore_errors,
+        watch,
+    } = args;
+    let output = output.as_str();
+    let keyring = key_source.as_deref() == Some("keyring");
+    
+    let compression = CompressionPlan::new(parse_method(&compression)?, compression_for)?;
+    // Only V10 and later have a path hash index; older versions don't store the seed
+    if path_hash_seed.is_some() && !matches!(version, Version::V10 | Version::V11) {
+        warn!("--path-hash-seed has no effect on a {:?} PAK, which has no path hash index", version);
+    }
+    
+    // Mount points are directory prefixes
+    if !mount_point.ends_with('/') {
+        mount_point.push('/');
+    }
+    
+    let inputs = match (&manifest_path, &input_root) {
+        (Some(path), _) => {
+            let path = Path::new(path);
+            info!("Using manifest: {}", path.display());
+            manifest_inputs(&mt_pak_extract::read_manifest(path)?, path.parent().unwrap_or(Path::new("")))
+        }
+        (None, Some(root)) => {
+            let mount_dir = mt_pak_extract::mount_layout_dir(&mount_point)?;
+            files.iter()
+                .map(|file| Ok(Input { local: file.clone(), pak_path: pak_path_under_root(file, root, &mount_dir)? }))
+                .collect::<Result<Vec<_>, String>>()?
+        }
+        (None, None) => {
+            let mapping = match &mapping_path {
+                Some(path) => load_mapping(Path::new(path))?,
+                None => Mapping::default(),
+            };
+            files.iter()
+                .map(|file| Ok(Input { local: file.clone(), pak_path: pak_path_for_input(file, &mapping)? }))
+                .collect::<Result<Vec<_>, String>>()?
+        }
+    };
+    if inputs.is_empty() {
+        return Err("The manifest lists no extracted assets".into());
+    }
+    
+    // The key encrypts the output, and opens the base PAK's index for --validate-against
+    let key = if encrypt || validate_against.is_some() {
+        let key_source = mt_pak_extract::KeySource {
+            key: key.as_deref(),
+            keyfile: keyfile.as_deref().map(Path::new),
+            keyring,
+        };
+        Some(mt_pak_extract::load_key(&key_source, &user_config::load()?)?)
+    } else {
+        None
+    };
+    if let (Some(base), Some(key)) = (&validate_against, &key) {
+        validate_inputs(base, key, &inputs, &mount_point)?;
+    }
+    
+    if dry_run {
+        return print_plan(output, &inputs, &mount_point);
+    }
+    
+    let aes_key = if encrypt { key } else { None };
+    if aes_key.is_some() {
+        info!("Encrypting with the AES key");
+    }
+    
+    // A change to any of these makes --only-changed repack everything
+    let inputs_source = match (&manifest_path, &input_root, &mapping_path) {
+        (Some(path), _, _) => format!("manifest {}", path),
+        (None, Some(root), _) => format!("input root {}", root),
+        (None, None, Some(path)) => format!("mapping {}", mt_pak_extract::sha256_hex(&fs::read(path)?)),
+        (None, None, None) => "built-in mapping".to_string(),
+    };
+    let settings = format!(
+        "{:?}, {:?}, mount point {}, path hash seed {:?}, encrypted {}, sign {}, {}",
+        version, compression, mount_point, path_hash_seed, aes_key.is_some(), sign, inputs_source,
+    );
+    
+    let repack = Repack { aes_key, version, compression, mount_point, path_hash_seed, sign, only_changed, settings, ignore_errors };
+    repack.build(output, &inputs)?;
+    
+    if watch {
+        watch_inputs(&repack, output, &inputs)?;
+    }
+    Ok(())
+}
+
+/// Everything needed to (re)build the output PAK from its inputs
+struct Repack {
+    aes_key: Option<Aes256>,
+    version: Version,
+    compression: CompressionPlan,
+    mount_point: String,
+    path_hash_seed: Option<u64>,
+    /// Write a `.sig` next to the PAK
+    sign: bool,
+    /// Copy files unchanged since the last build from its PAK, and skip the build if none changed
+    only_changed: bool,
+    /// Description of the settings and mapping, which --only-changed compares between builds
+    settings: String,
+    ignore_errors: bool,
+}
+
+/// How the entries of the output PAK are compressed. repak writes every compressed
+/// entry of a PAK with the same method, so per-extension rules can only turn it off
+/// (or on, when the default is none).
+#[derive(Debug)]
+struct CompressionPlan {
+    method: Option<Compression>,
+    /// Whether files without a rule are compressed
+    by_default: bool,
+    /// Lowercase extension with its dot -> whether those files are compressed
+    by_extension: Vec<(String, bool)>,
+}
+
+impl CompressionPlan {
+    fn new(default: Option<Compression>, by_extension: Vec<(String, Option<Compression>)>) -> Result<Self, String> {
+        let mut method = default;
+        for (ext, ext_method) in &by_extension {
+            match (method, ext_method) {
+                (Some(m), Some(e)) if m != *e => {
+                    return Err(format!(
+                        "A PAK holds a single compression method, but {:?} and {:?} (for {}) were both asked for",
+                        m, e, ext,
+                    ));
+                }
+                (None, Some(e)) => method = Some(*e),
+                _ => {}
+            }
+        }
+        Ok(CompressionPlan {
+            method,
+            by_default: default.is_some(),
+            by_extension: by_extension.into_iter().map(|(ext, m)| (ext, m.is_some())).collect(),
+        })
+    }
+    
+    /// Whether the file packed to `pak_path` is compressed
+    fn compresses(&self, pak_path: &str) -> bool {
+        let pak_path = pak_path.to_lowercase();
+        self.by_extension.iter()
+            .find(|(ext, _)| pak_path.ends_with(ext.as_str()))
+            .map_or(self.by_default, |&(_, compress)| compress)
+    }
+}
+
+impl Repack {
+    /// Write `output` from `inputs` and their siblings, then report the compression achieved
+    fn build(&self, output: &str, inputs: &[Input]) -> Result<(), Box<dyn std::error::Error>> {
+        // Check every input before creating the output, so a typo doesn't leave a partial PAK
+        let missing: Vec<&str> = inputs.iter()
+            .map(|input| input.local.as_str())
+            .filter(|local| !Path::new(local).is_file())
+            .collect();
+        if !missing.is_empty() {
+            if !self.ignore_errors || missing.len() == inputs.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} input files not found: {}", missing.len(), missing.join(", ")),
+                ).into());
+            }
+            for input in &missing {
+                warn!("Skipping missing input: {}", input);
+            }
+        }
+        
+        // The inputs and the siblings next to them, in packing order
+        let mut files = Vec::new();
+        for Input { local, pak_path } in inputs.iter().filter(|input| !missing.contains(&input.local.as_str())) {
+            files.push(PackedInput { local: local.clone(), pak_path: pak_path.clone(), sibling: false });
+            for ext in SIBLING_EXTENSIONS {
+                let (Some(sibling_input), Some(sibling_pak_path)) = (sibling_path(local, ext), sibling_path(pak_path, ext)) else {
+                    continue;
+                };
+                if Path::new(&sibling_input).is_file() {
+                    files.push(PackedInput { local: sibling_input, pak_path: sibling_pak_path, sibling: true });
+                }
+            }
+        }
+        
+        // With --only-changed, files that match the last build are copied from its PAK
+        let previous = if self.only_changed { previous_build(output, &self.settings) } else { None };
+        let mut state = BuildState { version: STATE_VERSION, settings: self.settings.clone(), ..BuildState::default() };
+        // Without it no state is written, so the inputs are packed as changed without hashing them
+        let mut unchanged = vec![false; files.len()];
+        if self.only_changed {
+            for (file, same) in files.iter().zip(&mut unchanged) {
+                let record = previous.as_ref()
+                    .and_then(|previous| previous.files.get(&file.local))
+                    .filter(|record| record.pak_path == file.pak_path);
+                let (packed, file_same) = packed_file(file, record)?;
+                state.files.insert(file.local.clone(), packed);
+                *same = file_same;
+            }
+        }
+        if let Some(previous) = &previous
+            && unchanged.iter().all(|&same| same)
+            && previous.files.len() == files.len()
+        {
+            println!("Nothing changed since the last build; {} is up to date ({} files)", output, files.len());
+            return Ok(());
+        }
+        let mut base = match &previous {
+            Some(_) => match self.open_previous(output) {
+                Ok(base) => Some(base),
+                Err(e) => {
+                    warn!("Could not read the previous {} ({}); packing every file from disk", output, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        
+        let mut builder = PakBuilder::new().compression(self.compression.method);
+        if let Some(aes_key) = &self.aes_key {
+            builder = builder.key(aes_key.clone());
+        }
+        
+        // The previous PAK is still being read, so build next to it and replace it at the end
+        let target = if base.is_some() { format!("{}.partial", output) } else { output.to_string() };
+        info!("Writing {} ({:?}, compression: {:?})", output, self.version, self.compression.method);
+        let mut writer = builder
+            .writer(BufWriter::new(File::create(&target)?), self.version, self.mount_point.clone(), self.path_hash_seed);
+        
+        let mut count = 0;
+        let mut reused = 0;
+        for (file, &same) in files.iter().zip(&unchanged) {
+            let indent = if file.sibling { "    + " } else { "  " };
+            let data = match &mut base {
+                Some((pak, reader)) if same => {
+                    println!("{}{} -> {} (unchanged)", indent, file.local, file.pak_path);
+                    reused += 1;
+                    pak.get(&file.pak_path, reader)?
+                }
+                _ => {
+                    println!("{}{} -> {}", indent, file.local, file.pak_path);
+                    fs::read(&file.local)?
+                }
+            };
+            writer.write_file(&file.pak_path, self.compression.compresses(&file.pak_path), data)?;
+            count += 1;
+        }
+        
+        writer.write_index()?.flush()?;
+        // Close the previous PAK first; Windows won't replace a file that is still open
+        drop(base);
+        if target != output {
+            fs::rename(&target, output)?;
+        }
+        if self.only_changed {
+            let metadata = fs::metadata(output)?;
+            state.pak_size = metadata.len();
+            state.pak_modified = Some(metadata.modified()?);
+            fs::write(state_path(output), serde_json::to_string_pretty(&state)?)?;
+        }
+        
+        // Read the entry sizes back from the written index to report what compression achieved
+        let pak_index = index::read_index(&mut BufReader::new(File::open(output)?), self.aes_key.as_ref())?;
+        println!("\nCompression:");
+        for entry in &pak_index.entries {
+            let ratio = if entry.uncompressed_size == 0 {
+                100.0
+            } else {
+                entry.stored_size() as f64 / entry.uncompressed_size as f64 * 100.0
+            };
+            println!("  {} [{}]: {} -> {} bytes ({:.1}%)",
+                entry.path, entry.compression_name(), entry.uncompressed_size, entry.stored_size(), ratio);
+        }
+        let original: u64 = pak_index.entries.iter().map(|entry| entry.uncompressed_size).sum();
+        let packed: u64 = pak_index.entries.iter().map(|entry| entry.stored_size()).sum();
+        println!("  Total: {} -> {} bytes ({:.1}%)", original, packed, packed as f64 / original.max(1) as f64 * 100.0);
+        
+        if self.sign {
+            let sig = Signature::write_for(Path::new(output))?;
+            println!("\nSignature: {}", sig.display());
+        }
+        
+        println!("\n=== Packed {} files into {} (mount point {}) ===", count, output, self.mount_point);
+        if self.only_changed {
+            println!("Reused {} unchanged files from the previous build, repacked {}", reused, count - reused);
+        }
+        Ok(())
+    }
+    
+    /// The PAK the last build wrote, to copy unchanged files from
+    fn open_previous(&self, output: &str) -> Result<(PakReader, BufReader<File>), Box<dyn std::error::Error>> {
+        let mut reader = BufReader::new(File::open(output)?);
+        let mut builder = PakBuilder::new();
+        if let Some(aes_key) = &self.aes_key {
+            builder = builder.key(aes_key.clone());
+        }
+        Ok((builder.reader(&mut reader)?, reader))
+    }
+}
+
+/// What wakes up the watch loop
+enum WatchSignal {
+    Changed(notify::Result<notify::Event>),
+    Interrupted,
+}
+
+/// Rebuild `output` whenever an input or one of its siblings changes, until Ctrl-C.
+/// Build errors are reported and the loop keeps watching.
+fn watch_inputs(repack: &Repack, output: &str, inputs: &[Input]) -> Result<(), Box<dyn std::error::Error>> {
+    // Watch the folders rather than the files, so editors that save by replacing the
+    // file (and siblings created later) are still seen
+    let mut watched = HashSet::new();
+    let mut dirs = HashSet::new();
+    for Input { local, .. } in inputs {
+        let mut files = vec![local.clone()];
+        files.extend(SIBLING_EXTENSIONS.iter().filter_map(|ext| sibling_path(local, ext)));
+        for file in files {
+            let file = Path::new(&file);
+            let dir = match file.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let dir = fs::canonicalize(dir)?;
+            if let Some(name) = file.file_name() {
+                watched.insert(dir.join(name));
+            }
+            dirs.insert(dir);
+        }
+    }
+    
+    let (tx, rx) = mpsc::channel();
+    let fs_tx = tx.clone();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = fs_tx.send(WatchSignal::Changed(event));
+    })?;
+    for dir in &dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    // Finish the current build before exiting, so Ctrl-C never leaves a half-written PAK
+    ctrlc::set_handler(move || {
+        let _ = tx.send(WatchSignal::Interrupted);
+    })?;
+    
+    println!("\nWatching {} files in {} folders for changes (Ctrl-C to stop)", watched.len(), dirs.len());
+    loop {
+        let changed = match rx.recv()? {
+            WatchSignal::Interrupted => break,
+            WatchSignal::Changed(event) => is_relevant(&event?, &watched),
+        };
+        if !changed {
+            continue;
+        }
+        
+        // Editors often write a file in several steps; wait until it's been quiet for a while
+        let mut interrupted = false;
+        while let Ok(signal) = rx.recv_timeout(DEBOUNCE) {
+            if let WatchSignal::Interrupted = signal {
+                interrupted = true;
+                break;
+            }
+        }
+        if interrupted {
+            break;
+        }
+        
+        println!("\n[{}] Change detected, rebuilding {}", clock_time(), output);
+        let start = Instant::now();
+        match repack.build(output, inputs) {
+            Ok(()) => println!("[{}] Rebuilt in {:.2?}", clock_time(), start.elapsed()),
+            Err(e) => error!("Rebuild failed after {:.2?}: {}", start.elapsed(), e),
+        }
+    }
+    
+    println!("\nStopped watching");
+    Ok(())
+}
+
+/// Whether a file system event touches one of the watched files
+fn is_relevant(event: &notify::Event, watched: &HashSet<PathBuf>) -> bool {
+    !event.kind.is_access() && event.paths.iter().any(|path| watched.contains(path))
+}
+
+/// Current time of day as `HH:MM:SS` UTC
+fn clock_time() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) % 86_400;
+    format!("{:02}:{:02}:{:02} UTC", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Resolve every input like a real run and print where it would be packed, without
+/// creating the output. Fails if any input is missing.
+fn print_plan(output: &str, inputs: &[Input], mount_point: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Dry run: planning {} (mount point {})", output, mount_point);
+    let mut count = 0;
+    let mut missing = Vec::new();
+    for Input { local, pak_path } in inputs {
+        if !Path::new(local).is_file() {
+            println!("  {} ... NOT FOUND", local);
+            missing.push(local.as_str());
+            continue;
+        }
+        println!("  {} -> {}", local, pak_path);
+        count += 1;
+        
+        for ext in SIBLING_EXTENSIONS {
+            let (Some(sibling_input), Some(sibling_pak_path)) = (sibling_path(local, ext), sibling_path(pak_path, ext)) else {
+                continue;
+            };
+            if Path::new(&sibling_input).is_file() {
+                println!("    + {} -> {}", sibling_input, sibling_pak_path);
+                count += 1;
+            }
+        }
+    }
+    
+    println!("\n=== Dry run: {} files would be packed into {}, nothing written ===", count, output);
+    if !missing.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} input files not found: {}", missing.len(), missing.join(", ")),
+        ).into());
+    }
+    Ok(())
+}
+
+/// Warn about inputs whose internal path is not in `base`, the game PAK the mod is
+/// meant to override. Such an input adds a new file that the game never loads,
+/// which is almost always a mapping mistake.
+fn validate_inputs(base: &Path, key: &Aes256, inputs: &[Input], mount_point: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (pak, _) = mt_pak_extract::open_pak_file(base, key, false)?;
+    let base_files = pak.files();
+    let base_mount = pak.mount_point().trim_end_matches('/');
+    // Compare mounted paths, in case the mod uses a different mount point than the base
+    let mounted: HashSet<String> = base_files.iter().map(|file| format!("{}/{}", base_mount, file)).collect();
+    
+    let mut unmatched = 0;
+    for Input { local, pak_path } in inputs {
+        if mounted.contains(&format!("{}{}", mount_point, pak_path)) {
+            continue;
+        }
+        unmatched += 1;
+        match suggest::closest_assets(pak_path.trim_end_matches(".uasset"), &base_files, 1).first() {
+            Some(closest) => warn!("{} -> {} does not override anything in {} (closest: {})", local, pak_path, base.display(), closest),
+            None => warn!("{} -> {} does not override anything in {}", local, pak_path, base.display()),
+        }
+    }
+    if unmatched == 0 {
+        info!("All {} inputs override an entry in {}", inputs.len(), base.display());
+    } else {
+        warn!("{} of {} inputs would not override anything; check --mapping or --mount-point", unmatched, inputs.len());
+    }
+    Ok(())
+}
+
+/// A `--compression` method name, or `none`
+fn parse_method(name: &str) -> Result<Option<Compression>, String> {
+    if name.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    mt_pak_extract::parse_compression(name)
+        .map(Some)
+        .map_err(|_| format!("Unknown compression '{}' (expected none, zlib, gzip, zstd, lz4 or oodle)", name))
+}
+
+/// A `--compression-for` rule: `<ext>=<method>`, the extension lowercased with its dot
+fn parse_compression_for(rule: &str) -> Result<(String, Option<Compression>), String> {
+    let (ext, method) = rule.split_once('=').ok_or_else(|| format!("'{}' is not <ext>=<method>", rule))?;
+    Ok((format!(".{}", ext.trim_start_matches('.').to_lowercase()), parse_method(method)?))
+}
+
+/// A `--path-hash-seed`: decimal, or hex with a `0x` prefix
+fn parse_seed(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|e| format!("Invalid path hash seed '{}': {}", s, e))
+}
+
+/// Load mapping rules from a `.toml` file, or JSON for any other extension
+fn load_mapping(path: &Path) -> Result<Mapping, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let mapping = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str(&content).map_err(|e| format!("Invalid mapping {}: {}", path.display(), e))?
+    } else {
+        serde_json::from_str(&content).map_err(|e| format!("Invalid mapping {}: {}", path.display(), e))?
+    };
+    info!("Using mapping: {}", path.display());
+    Ok(mapping)
+}
+
+/// The extracted .uasset of every asset in a manifest, found relative to `dir` (the
+/// manifest's directory) and packed back to the entry it was extracted from. Manifests
+/// before version 6 don't record the entry, which is then rebuilt from `pak_path`.
+fn manifest_inputs(manifest: &Manifest, dir: &Path) -> Vec<Input> {
+    manifest.extracted.iter()
+        .map(|asset| Input {
+            local: dir.join(&asset.uasset).to_string_lossy().into_owned(),
+            pak_path: if asset.uasset_entry.is_empty() {
+                format!("{}.uasset", asset.pak_path)
+            } else {
+                asset.uasset_entry.clone()
+            },
+        })
+        .collect()
+}
+
+/// Internal PAK path for a local input file, which may use `\` or `/` separators
+fn pak_path_for_input(input_path: &str, mapping: &Mapping) -> Result<String, String> {
+    let input_path = normalize_slashes(input_path);
+    match input_path.rsplit('/').next() {
+        Some(file_name) if !file_name.is_empty() => Ok(get_pak_path(file_name, mapping)),
+        _ => Err(format!("Not a file: {}", input_path)),
+    }
+}
+
+/// Internal PAK path for an input laid out like `extract --out-layout mount`: its path
+/// under `root`, less the mount point's folder (`mount_dir`, e.g. `MotorTown/` or empty).
+/// Paths are compared as given, so pass the inputs and the root the same way.
+fn pak_path_under_root(input_path: &str, root: &str, mount_dir: &str) -> Result<String, String> {
+    let input = normalize_slashes(input_path);
+    let input = input.trim_start_matches("./");
+    let root = normalize_slashes(root);
+    let root = root.trim_start_matches("./").trim_end_matches('/');
+    let relative = if root.is_empty() || root == "." {
+        Some(input)
+    } else {
+        input.strip_prefix(root).and_then(|rest| rest.strip_prefix('/'))
+    };
+    let relative = relative.ok_or_else(|| format!("{} is not under --input-root {}", input_path, root))?;
+    relative.strip_prefix(mount_dir)
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} is not under the mount point's folder {} in --input-root", input_path, mount_dir))
+}
+
+/// Internal PAK path for a file: the folder of the first rule whose prefix matches
+/// its name, or the mapping's default folder. Folders may be written with `\`.
+fn get_pak_path(file_name: &str, mapping: &Mapping) -> String {
+    let folder = mapping.rules.iter()
+        .find(|rule| file_name.starts_with(&rule.prefix))
+        .map_or(&mapping.default_folder, |rule| &rule.folder);
+    format!("{}/{}", normalize_slashes(folder).trim_end_matches('/'), file_name)
+}
+
+/// Path of a `.uasset`'s sibling with extension `ext`. Only the final `.uasset`
+/// is replaced, so directories with `.uasset` in their name are left alone.
+fn sibling_path(uasset_path: &str, ext: &str) -> Option<String> {
+    uasset_path.ends_with(".uasset").then(|| normalize_asset_path(uasset_path).with_extension(ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn sibling_path_replaces_only_the_final_extension() {
+        assert_eq!(
+            sibling_path("My.uasset.backup/File.uasset", ".uexp").as_deref(),
+            Some("My.uasset.backup/File.uexp"),
+        );
+        assert_eq!(sibling_path("My.uasset.backup/File.txt", ".uexp"), None);
+    }
+    
+    #[test]
+    fn backslash_inputs_map_to_forward_slash_pak_paths() {
+        let mapping = Mapping::default();
+        assert_eq!(
+            pak_path_for_input(r"C:\Users\me\mods\Cargos.uasset", &mapping).unwrap(),
+            "MotorTown/Content/DataAsset/Cargos.uasset",
+        );
+        assert_eq!(
+            pak_path_for_input(r"mods/delivery\Factory_Sawmill.uasset", &mapping).unwrap(),
+            "MotorTown/Content/Objects/Mission/Delivery/DeliveryPoint/Factory_Sawmill.uasset",
+        );
+        assert_eq!(
+            sibling_path(&pak_path_for_input(r"mods\Cargos.uasset", &mapping).unwrap(), ".uexp").as_deref(),
+            Some("MotorTown/Content/DataAsset/Cargos.uexp"),
+        );
+        assert!(pak_path_for_input(r"mods\", &mapping).is_err());
+    }
+    
+    #[test]
+    fn manifest_inputs_keep_the_extracted_pak_paths() {
+        let manifest: Manifest = serde_json::from_str(r#"{
+            "version": 6,
+            "extracted": [
+                { "name": "Cargos", "pak_path": "MotorTown/Content/DataAsset/Cargos", "uasset": "Cargos.uasset", "uexp": "Cargos.uexp" },
+                { "name": "Cargos", "pak_path": "MotorTown/Content/Other/Cargos", "uasset": "Cargos_2.uasset", "uexp": null,
+                  "uasset_entry": "MotorTown/Content/Other/Cargos.uasset" }
+            ]
+        }"#).unwrap();
+        let inputs = manifest_inputs(&manifest, Path::new("out"));
+        let planned: Vec<(&str, &str)> = inputs.iter().map(|input| (input.local.as_str(), input.pak_path.as_str())).collect();
+        assert_eq!(planned, [
+            (Path::new("out").join("Cargos.uasset").to_str().unwrap(), "MotorTown/Content/DataAsset/Cargos.uasset"),
+            (Path::new("out").join("Cargos_2.uasset").to_str().unwrap(), "MotorTown/Content/Other/Cargos.uasset"),
+        ]);
+        assert_eq!(sibling_path(&inputs[1].pak_path, ".uexp").as_deref(), Some("MotorTown/Content/Other/Cargos.uexp"));
+    }
+    
+    #[test]
+    fn compression_rules_override_the_default_by_extension() {
+        let plan = CompressionPlan::new(
+            Some(Compression::Zstd),
+            vec![(".ubulk".to_string(), None), (".uptnl".to_string(), None)],
+        ).unwrap();
+        assert_eq!(plan.method, Some(Compression::Zstd));
+        assert!(plan.compresses("MotorTown/Content/DataAsset/Cargos.uasset"));
+        assert!(!plan.compresses("MotorTown/Content/Textures/T_Truck.UBULK"));
+        
+        let plan = CompressionPlan::new(None, vec![(".uexp".to_string(), Some(Compression::Zlib))]).unwrap();
+        assert_eq!(plan.method, Some(Compression::Zlib));
+        assert!(plan.compresses("Cargos.uexp"));
+        assert!(!plan.compresses("Cargos.uasset"));
+        
+        assert!(CompressionPlan::new(Some(Compression::Zlib), vec![(".uexp".to_string(), Some(Compression::Zstd))]).is_err());
+    }
+    
+    #[test]
+    fn path_hash_seed_accepts_decimal_and_hex() {
+        assert_eq!(parse_seed("305419896"), Ok(0x12345678));
+        assert_eq!(parse_seed("0x12345678"), Ok(305419896));
+        assert_eq!(parse_seed("0XFFFFFFFFFFFFFFFF"), Ok(u64::MAX));
+        assert!(parse_seed("12345678h").is_err());
+        assert!(parse_seed("0x").is_err());
+    }
+    
+    #[test]
+    fn input_root_paths_invert_the_mount_layout() {
+        assert_eq!(
+            pak_path_under_root("out/MotorTown/Content/DataAsset/Cargos.uasset", "out", ""),
+            Ok("MotorTown/Content/DataAsset/Cargos.uasset".to_string()),
+        );
+        assert_eq!(
+            pak_path_under_root(".\\out\\MotorTown\\Content\\Cargos.uasset", "./out/", "MotorTown/"),
+            Ok("Content/Cargos.uasset".to_string()),
+        );
+        assert_eq!(pak_path_under_root("MotorTown/Content/Cargos.uasset", ".", "MotorTown/"), Ok("Content/Cargos.uasset".to_string()));
+        assert!(pak_path_under_root("elsewhere/Cargos.uasset", "out", "").is_err());
+        assert!(pak_path_under_root("outside/Cargos.uasset", "out", "").is_err());
+        assert!(pak_path_under_root("out/Engine/Content/Cargos.uasset", "out", "MotorTown/").is_err());
+    }
+    
+    #[test]
+    fn unchanged_inputs_are_recognized_by_mtime_or_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("Cargos.uasset");
+        fs::write(&local, b"cargo").unwrap();
+        let file = PackedInput {
+            local: local.to_string_lossy().into_owned(),
+            pak_path: "MotorTown/Content/DataAsset/Cargos.uasset".to_string(),
+            sibling: false,
+        };
+        
+        let (record, same) = packed_file(&file, None).unwrap();
+        assert!(!same);
+        assert!(packed_file(&file, Some(&record)).unwrap().1);
+        
+        // Touched but identical: the hash decides
+        let touched = PackedFile { modified: UNIX_EPOCH, ..record.clone() };
+        assert!(packed_file(&file, Some(&touched)).unwrap().1);
+        
+        fs::write(&local, b"trucks").unwrap();
+        let (edited, same) = packed_file(&file, Some(&record)).unwrap();
+        assert!(!same);
+        assert_ne!(edited.sha256, record.sha256);
+    }
+    
+    #[test]
+    fn backslash_mapping_folders_are_normalized() {
+        let mapping = Mapping {
+            default_folder: r"MotorTown\Content\DataAsset\".to_string(),
+            rules: vec![PathRule { prefix: "Vehicle_".to_string(), folder: r"MotorTown\Content/Vehicles".to_string() }],
+        };
+        assert_eq!(get_pak_path("Cargos.uasset", &mapping), "MotorTown/Content/DataAsset/Cargos.uasset");
+        assert_eq!(get_pak_path("Vehicle_Truck.uasset", &mapping), "MotorTown/Content/Vehicles/Vehicle_Truck.uasset");
+    }
+}
@@ -0,0 +1,320 @@
+//! Verify that a PAK opens and that every entry extracts cleanly.
+//!
+//! A `<pak>.sig` next to the PAK is checked too: every chunk hash in it must match the PAK.
+//! Run with --help for the options.
+//!
+//! Exits like mt-pak-extract: 5 if some entries failed to extract, 1 on checksum, entry hash
+//! or signature mismatches.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::Parser;
+use log::{error, info, warn, LevelFilter};
+use rayon::prelude::*;
+
+use mt_pak_extract::container::Container;
+use mt_pak_extract::error::{self, ExtractError};
+use mt_pak_extract::pak_file::PakFile;
+use mt_pak_extract::signature::{self, Signature};
+use mt_pak_extract::{index, user_config};
+use repak::Version;
+
+/// Verify that a PAK opens and that every entry extracts cleanly
+#[derive(Parser)]
+#[command(name = "verify_pak")]
+struct Args {
+    /// PAK file to verify
+    pak: String,
+    
+    /// Where to write the extracted entries
+    #[arg(long, default_value = "verify_out")]
+    out: PathBuf,
+    
+    /// Warn if the PAK's version differs, e.g. V11
+    #[arg(long, value_parser = mt_pak_extract::parse_version)]
+    expect_version: Option<Version>,
+    
+    /// JSON object of entry path -> SHA-256; fail unless every listed entry is in the PAK
+    /// with that hash
+    #[arg(long, value_name = "FILE")]
+    checksums: Option<PathBuf>,
+    
+    /// Recompute each entry's SHA-1 over its data as stored in the PAK (compressed and, if
+    /// encrypted, encrypted) and fail unless it matches the hash in the entry's header, which
+    /// catches corruption of the PAK file itself
+    #[arg(long)]
+    check_entry_hashes: bool,
+    
+    /// AES key as hex, instead of the KEY environment variable (overrides --keyfile)
+    #[arg(long)]
+    key: Option<String>,
+    
+    /// File with the AES key, as raw hex or {"key": "0x..."}
+    #[arg(long)]
+    keyfile: Option<String>,
+    
+    /// Look for the key in the OS credential store before KEY with `keyring`
+    #[arg(long, value_parser = ["env", "keyring"])]
+    key_source: Option<String>,
+    
+    /// Entries extracted in parallel, each worker with its own PAK handle (default: one per CPU)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+    
+    /// Read the PAK through a memory map instead of a buffer
+    #[arg(long)]
+    mmap: bool,
+    
+    /// Exit successfully even if some entries fail to extract
+    #[arg(long)]
+    ignore_errors: bool,
+}
+
+/// What verifying one entry found
+enum Checked {
+    /// Extracted; the SHA-256 is only computed for entries listed in --checksums
+    Extracted { sha256: Option<String> },
+    Failed(ExtractError),
+}
+
+/// What --check-entry-hashes found for one entry
+enum HashCheck {
+    NotChecked,
+    /// The PAK stores no hash for the entry (all zeros)
+    NotRecorded,
+    Matches,
+    Differs { stored: String, computed: String },
+}
+
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            // Usage errors exit 1 like other unexpected errors (2 means asset not found)
+            let _ = e.print();
+            return if e.use_stderr() { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+        }
+    };
+    
+    mt_pak_extract::init_logging(LevelFilter::Info);
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::from(error::exit_code(e.as_ref()))
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    println!("=== MotorTown PAK Verifier ===");
+    
+    let Args {
+        pak: pak_path,
+        out: out_dir,
+        expect_version: expected_version,
+        checksums: checksums_path,
+        check_entry_hashes,
+        key,
+        keyfile,
+        key_source,
+        jobs,
+        mmap,
+        ignore_errors,
+    } = args;
+    let pak_path = pak_path.as_str();
+    let jobs = jobs.map_or(0, usize::from);
+    let checksums: Option<BTreeMap<String, String>> = match checksums_path {
+        Some(checksums_path) => {
+            let content = fs::read_to_string(&checksums_path)?;
+            Some(serde_json::from_str(&content).map_err(|e| format!("Invalid checksums {}: {}", checksums_path.display(), e))?)
+        }
+        None => None,
+    };
+    
+    // Load AES key: --key, --keyfile, the OS credential store (--key-source keyring), then .env, then the user config
+    let key_source = mt_pak_extract::KeySource {
+        key: key.as_deref(),
+        keyfile: keyfile.as_deref().map(Path::new),
+        keyring: key_source.as_deref() == Some("keyring"),
+    };
+    let aes_key = mt_pak_extract::load_key(&key_source, &user_config::load()?)?;
+    
+    info!("Opening PAK file: {}", pak_path);
+    if !Path::new(pak_path).exists() {
+        return Err(ExtractError::PakNotFound(pak_path.into()).into());
+    }
+    if mt_pak_extract::is_iostore(Path::new(pak_path)) {
+        return Err(ExtractError::IoStoreUnsupported(pak_path.into()).into());
+    }
+    
+    // Read the version from the footer first, so it is reported even if repak can't open the PAK
+    let version = index::detect_version(&mut BufReader::new(File::open(pak_path)?))
+        .map_err(|e| format!("{}: {}", pak_path, e))?;
+    println!("  Version: {:?} (detected from the footer)", version);
+    
+    let (pak, _) = mt_pak_extract::open_pak_file(Path::new(pak_path), &aes_key, mmap)
+        .map_err(|e| format!("{} (a {:?} PAK): {}", pak_path, version, e))?;
+    if let Some(seed) = pak.path_hash_seed() {
+        println!("  Path hash seed: 0x{:016X}", seed);
+    }
+    let pak: &dyn Container = &pak;
+    println!("  Mount point: {}", pak.mount_point());
+    
+    if let Some(expected) = expected_version {
+        if version == expected {
+            println!("  Version matches expected {:?}", expected);
+        } else {
+            warn!("PAK version is {:?} but {:?} was expected; a version mismatch is a common reason for mods not loading", version, expected);
+        }
+    }
+    
+    let files = pak.files();
+    println!("  Entries: {}", files.len());
+    println!();
+    
+    // Where each entry's header and data are, for --check-entry-hashes
+    let pak_index = if check_entry_hashes {
+        Some(index::read_index(&mut BufReader::new(File::open(pak_path)?), Some(&aes_key))?)
+    } else {
+        None
+    };
+    
+    let out_dir = out_dir.as_path();
+    let mut ok = 0;
+    let mut failed = 0;
+    let mut mismatches = Vec::new();
+    let start = Instant::now();
+    
+    // Entries are decoded in parallel, each worker reading through its own handle on
+    // the PAK; results are reported afterwards in PAK order
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
+    let checked: Vec<(Checked, HashCheck)> = pool.install(|| {
+        files.par_iter()
+            .map_init(
+                || PakFile::open(Path::new(pak_path), mmap),
+                |file, path| -> io::Result<(Checked, HashCheck)> {
+                    let file = file.as_mut().map_err(|e| io::Error::new(e.kind(), e.to_string()))?;
+                    // Checked before extracting, so corruption that makes extraction fail is named too
+                    let hash = match pak_index.as_ref().and_then(|pak_index| Some((pak_index, pak_index.find(path)?))) {
+                        Some((pak_index, entry)) => match index::stored_hash(file, pak_index.version, entry)? {
+                            None => HashCheck::NotRecorded,
+                            Some(stored) => match index::data_hash(file, entry)? {
+                                computed if computed == stored => HashCheck::Matches,
+                                computed => HashCheck::Differs { stored: hex::encode(stored), computed: hex::encode(computed) },
+                            },
+                        },
+                        None => HashCheck::NotChecked,
+                    };
+                    if let Err(e) = mt_pak_extract::check_entry_path(path) {
+                        return Ok((Checked::Failed(e), hash));
+                    }
+                    let data = match pak.get(path, file) {
+                        Ok(data) => data,
+                        Err(e) => return Ok((Checked::Failed(e), hash)),
+                    };
+                    let out_path = out_dir.join(path);
+                    if let Some(parent) = out_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&out_path, &data)?;
+                    let listed = checksums.as_ref().is_some_and(|c| c.contains_key(path));
+                    Ok((Checked::Extracted { sha256: listed.then(|| mt_pak_extract::sha256_hex(&data)) }, hash))
+                },
+            )
+            .collect::<io::Result<_>>()
+    })?;
+    
+    let mut hash_matched = 0;
+    let mut hash_unrecorded = 0;
+    let mut hash_mismatches = Vec::new();
+    for (path, (checked, hash)) in files.iter().zip(checked) {
+        match hash {
+            HashCheck::NotChecked => {}
+            HashCheck::NotRecorded => hash_unrecorded += 1,
+            HashCheck::Matches => hash_matched += 1,
+            HashCheck::Differs { stored, computed } => {
+                hash_mismatches.push(format!("{}: stored {}, computed {}", path, stored, computed));
+            }
+        }
+        match checked {
+            Checked::Extracted { sha256 } => {
+                ok += 1;
+                if let (Some(expected), Some(actual)) = (checksums.as_ref().and_then(|c| c.get(path)), sha256)
+                    && !actual.eq_ignore_ascii_case(expected)
+                {
+                    mismatches.push(format!("{}: expected {}, got {}", path, expected, actual));
+                }
+            }
+            Checked::Failed(e) => {
+                error!("{}", e);
+                failed += 1;
+                if checksums.as_ref().is_some_and(|c| c.contains_key(path)) {
+                    mismatches.push(format!("{}: could not be read", path));
+                }
+            }
+        }
+    }
+    
+    println!("\n=== Verified {} entries in {:.2?}: {} OK, {} failed ===", files.len(), start.elapsed(), ok, failed);
+    println!("Extracted to: {}", out_dir.display());
+    
+    if check_entry_hashes {
+        println!("\nEntry hashes: {} match, {} mismatched, {} not recorded", hash_matched, hash_mismatches.len(), hash_unrecorded);
+        for mismatch in &hash_mismatches {
+            println!("  MISMATCH: {}", mismatch);
+        }
+        if !hash_mismatches.is_empty() {
+            return Err(format!("{} entries don't match the hash stored in the PAK; the PAK file is damaged", hash_mismatches.len()).into());
+        }
+    }
+    
+    if let Some(checksums) = &checksums {
+        for path in checksums.keys().filter(|path| !files.contains(path)) {
+            mismatches.push(format!("{}: not in the PAK", path));
+        }
+        println!("\nChecksums: {} listed, {} mismatched", checksums.len(), mismatches.len());
+        for mismatch in &mismatches {
+            println!("  MISMATCH: {}", mismatch);
+        }
+        if !mismatches.is_empty() {
+            return Err(format!("{} entries do not match the expected checksums", mismatches.len()).into());
+        }
+    }
+    
+    let sig_path = signature::sig_path(Path::new(pak_path));
+    if sig_path.is_file() {
+        check_signature(Path::new(pak_path), &sig_path)?;
+    }
+    
+    if failed > 0 && !ignore_errors {
+        return Err(ExtractError::PartialFailure { failed }.into());
+    }
+    Ok(())
+}
+
+/// Compare the chunk hashes in `sig_path` with the PAK's. The RSA-encrypted hash can't
+/// be checked without the game's public key, so it is only reported.
+fn check_signature(pak_path: &Path, sig_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let expected = Signature::read(BufReader::new(File::open(sig_path)?))
+        .map_err(|e| format!("{}: {}", sig_path.display(), e))?;
+    let actual = Signature::of_pak(BufReader::new(File::open(pak_path)?))?;
+    let mismatched = expected.mismatched_chunks(&actual);
+    println!("\nSignature {}: {} chunks, {} mismatched", sig_path.display(), expected.chunk_hashes.len(), mismatched.len());
+    if expected.encrypted_hash.is_empty() {
+        println!("  No encrypted hash (written by repack --sign); only the chunk hashes were checked");
+    }
+    for chunk in &mismatched {
+        let offset = *chunk as u64 * signature::CHUNK_SIZE as u64;
+        println!("  MISMATCH: chunk {} (bytes from 0x{:X})", chunk, offset);
+    }
+    if !mismatched.is_empty() {
+        return Err(format!("{} does not match {}", sig_path.display(), pak_path.display()).into());
+    }
+    Ok(())
+}
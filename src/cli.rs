@@ -0,0 +1,450 @@
+//! Command-line interface of the extractor.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use log::LevelFilter;
+use serde::Deserialize;
+use mt_pak_extract::batch::CollisionPolicy;
+
+#[derive(Parser)]
+#[command(name = "mt-pak-extract", about = "MotorTown PAK Asset Extractor")]
+pub struct Cli {
+    /// PAK file to read (default: the user config's "pak", then MotorTown-WindowsServer.pak).
+    /// Repeat to read several PAKs for list, search and extract; later ones override earlier ones.
+    #[arg(long, global = true)]
+    pub pak: Vec<PathBuf>,
+    
+    /// AES key as hex (overrides --keyfile and the KEY environment variable)
+    #[arg(long, global = true)]
+    pub key: Option<String>,
+    
+    /// File with the AES key, as raw hex or {"key": "0x..."}
+    #[arg(long, global = true)]
+    pub keyfile: Option<PathBuf>,
+    
+    /// Where to look for the AES key when --key and --keyfile aren't given (default: env)
+    #[arg(long, global = true, value_enum)]
+    pub key_source: Option<KeyOrigin>,
+    
+    /// Only extract (or list) entries whose data is encrypted
+    #[arg(long, global = true, alias = "encrypted-only", conflicts_with = "only_plaintext")]
+    pub only_encrypted: bool,
+    
+    /// Only extract (or list) entries whose data is not encrypted
+    #[arg(long, global = true, alias = "plaintext-only")]
+    pub only_plaintext: bool,
+    
+    /// Output format for listings: human-readable text or one JSON object per line
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+    
+    /// text: results on stdout for people. json: one JSON result object on stdout
+    /// (files written, errors and timing), with everything else on stderr.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text, conflicts_with = "format")]
+    pub output_format: OutputFormat,
+    
+    /// Oodle library (oo2core) for Oodle-compressed entries (default: the OODLE_LIB environment variable)
+    #[cfg(feature = "oodle")]
+    #[arg(long, global = true)]
+    pub oodle: Option<PathBuf>,
+    
+    /// Memory-map the PAKs instead of reading them through a buffer
+    /// (faster for many reads; the PAK must not change while it is open)
+    #[arg(long, global = true)]
+    pub mmap: bool,
+    
+    /// Parse the PAK index for list, search, stats and tree instead of reading it
+    /// from the index cache (which is still rebuilt whenever a PAK changes)
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+    
+    /// Before an extracted file overwrites one with different content, keep the
+    /// old file as <name>.bak (for edits made in the output directory)
+    #[arg(long, global = true)]
+    pub backup: bool,
+    
+    /// Exit successfully even if some assets or entries failed to extract
+    /// (batch still lists them in the manifest)
+    #[arg(long, global = true)]
+    pub ignore_errors: bool,
+    
+    /// Show more diagnostics on stderr (-vv for trace output)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+    
+    /// Only show warnings and errors on stderr
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    
+    /// Open the PAKs once and browse them at a prompt (ls, search, get, info)
+    /// instead of running a subcommand
+    #[arg(short, long)]
+    pub interactive: bool,
+    
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Ndjson,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Where extracted files go under the output directory
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutLayout {
+    /// Under the entry's internal path (relative to the mount point) with --preserve-paths,
+    /// --all and --prefix; flat otherwise
+    Internal,
+    /// Under the path relative to the game root: the mount point without its `../`
+    /// prefix, then the internal path. Implies --preserve-paths.
+    Mount,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ManifestFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyOrigin {
+    /// The key environment variable (or .env), then the user config
+    Env,
+    /// The OS credential store (see store-key), then the same as env
+    Keyring,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ListSort {
+    Name,
+    Size,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Show all DataAsset files in the PAK
+    List {
+        /// Show them as a directory tree with sizes
+        #[arg(long)]
+        tree: bool,
+        
+        /// Print a JSON array of entries with their sizes, compression and encryption
+        #[arg(long, conflicts_with = "tree")]
+        json: bool,
+        
+        /// List every entry in the PAK, not only DataAsset .uasset files
+        #[arg(long)]
+        all: bool,
+        
+        /// Order by path, or by uncompressed size with the largest first (default: PAK order)
+        #[arg(long, value_enum)]
+        sort: Option<ListSort>,
+        
+        /// Skip this many results first (after filtering and sorting)
+        #[arg(long, default_value_t = 0)]
+        skip: usize,
+        
+        /// Show at most this many results
+        #[arg(long)]
+        limit: Option<usize>,
+        
+        /// Print only the number of listed files
+        #[arg(long, conflicts_with_all = ["tree", "json", "sort", "skip", "limit"])]
+        count_only: bool,
+    },
+    /// Show every entry in the PAK as a directory tree with file counts and sizes
+    Tree {
+        /// Only show this many folder levels
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        depth: Option<u32>,
+        
+        /// Print the tree as nested JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Count entries and sizes per top-level folder and extension, and show the largest files
+    Stats,
+    /// Find assets whose path contains a pattern, or matches it as a glob if it
+    /// contains * ? [ or { (case-insensitive by default, e.g. "MotorTown/Content/**/Vehicle_*")
+    Search {
+        #[arg(required_unless_present = "regex")]
+        pattern: Option<String>,
+        
+        /// Match the full internal path against a regular expression instead
+        #[arg(long, conflicts_with = "pattern")]
+        regex: Option<String>,
+        
+        /// Match the pattern's case exactly (regexes always do; use (?i) to ignore case)
+        #[arg(long, conflicts_with = "regex")]
+        case_sensitive: bool,
+        
+        /// Skip this many results first (after filtering and sorting)
+        #[arg(long, default_value_t = 0)]
+        skip: usize,
+        
+        /// Show at most this many results
+        #[arg(long)]
+        limit: Option<usize>,
+        
+        /// Print only the number of matching assets
+        #[arg(long, conflicts_with_all = ["skip", "limit"])]
+        count_only: bool,
+    },
+    /// Extract a single asset's .uasset/.uexp to the current directory, or everything with --all
+    Extract {
+        /// Internal asset path, e.g. MotorTown/Content/DataAsset/Cargos
+        #[arg(required_unless_present_any = ["by_package", "by_hash", "all", "prefix", "stdin"])]
+        asset: Option<String>,
+        
+        /// Package path instead of an internal path (e.g. /Game/DataAsset/Cargos)
+        #[arg(long, conflicts_with = "asset")]
+        by_package: Option<String>,
+        
+        /// Mount point to use for --by-package instead of the PAK's own
+        #[arg(long, requires = "by_package")]
+        assume_mount: Option<String>,
+        
+        /// Extract the entry with this 64-bit path hash to <hash>.bin
+        #[arg(long, conflicts_with_all = ["asset", "by_package"])]
+        by_hash: Option<String>,
+        
+        /// Extract every entry in the PAK (not only .uasset/.uexp) to the output
+        /// directory, keeping the internal directory structure
+        #[arg(long, conflicts_with_all = ["asset", "by_package", "by_hash"])]
+        all: bool,
+        
+        /// Extract every entry under this internal folder (e.g. MotorTown/Content/DataAsset),
+        /// keeping the internal directory structure like --all
+        #[arg(long, conflicts_with_all = ["asset", "by_package", "by_hash", "all"])]
+        prefix: Option<String>,
+        
+        /// Extract the assets whose paths are read from stdin, one per line, like
+        /// batch without a config (writes manifest.json too)
+        #[arg(long, conflicts_with_all = ["asset", "by_package", "by_hash", "all", "prefix"])]
+        stdin: bool,
+        
+        /// With --all or --prefix: only extract entries with this extension, e.g. .ubulk
+        /// (repeatable)
+        #[arg(long = "ext", value_name = "EXT")]
+        ext: Vec<String>,
+        
+        /// With --all or --prefix: write entries to disk as they are read instead of
+        /// loading each one into memory first (for large .ubulk files)
+        #[arg(long)]
+        stream: bool,
+        
+        /// With --all or --prefix: skip this many entries first (in PAK order)
+        #[arg(long, default_value_t = 0)]
+        skip: usize,
+        
+        /// With --all or --prefix: extract at most this many entries
+        #[arg(long)]
+        limit: Option<usize>,
+        
+        /// With --all, --prefix or --stdin: extraction worker threads (default: one per CPU)
+        #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: Option<u16>,
+        
+        /// Write under the asset's internal path instead of just its file name
+        #[arg(long)]
+        preserve_paths: bool,
+        
+        /// Only extract the .uasset, not its .uexp (enough to read the header, but
+        /// not to parse the properties or repack the asset)
+        #[arg(long, conflicts_with_all = ["by_hash", "all", "prefix"])]
+        exclude_uexp: bool,
+        
+        /// Lay files out under their internal path, or under the mount point's
+        /// game-relative path like `repack --input-root` expects
+        #[arg(long, value_enum, default_value_t = OutLayout::Internal, conflicts_with = "by_hash")]
+        out_layout: OutLayout,
+        
+        /// Break a stale lock on the output directory
+        #[arg(long)]
+        force: bool,
+        
+        /// Output directory, created if missing (default: the user config's out_dir, then out/)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Batch extract the assets listed in a JSON config
+    Batch {
+        /// Assets to extract; repeat to extract the assets of several configs, each once.
+        /// `extract --stdin` runs a batch without one.
+        #[arg(long, required = true)]
+        config: Vec<PathBuf>,
+        
+        /// Check extracted entries against the hashes stored in the PAK
+        #[arg(long)]
+        verify: bool,
+        
+        /// Merge into an existing manifest.json instead of replacing it
+        #[arg(long)]
+        append_manifest: bool,
+        
+        /// Sort manifest entries by PAK path for stable, diffable output
+        #[arg(long)]
+        sort_manifest: bool,
+        
+        /// Write manifest.json, or manifest.csv with one row per extracted asset
+        #[arg(long, value_enum, default_value_t = ManifestFormat::Json)]
+        manifest_format: ManifestFormat,
+        
+        /// Extracted files buffered for the disk writer
+        #[arg(long, default_value_t = 16)]
+        write_queue: usize,
+        
+        /// Break a stale lock on the output directory
+        #[arg(long)]
+        force: bool,
+        
+        /// Recreate the PAK's directory tree under the output directory instead of writing flat
+        #[arg(long)]
+        preserve_paths: bool,
+        
+        /// Only extract each asset's .uasset, not its .uexp (recorded as "uexp": null
+        /// in the manifest; the result can't be repacked into a loadable asset)
+        #[arg(long)]
+        exclude_uexp: bool,
+        
+        /// Lay files out under their internal path, or under the mount point's
+        /// game-relative path like `repack --input-root` expects
+        #[arg(long, value_enum, default_value_t = OutLayout::Internal)]
+        out_layout: OutLayout,
+        
+        /// Extraction worker threads (default: one per CPU)
+        #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: Option<u16>,
+        
+        /// Check the config and print what would be extracted where, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// What to do when assets written flat share a file name
+        #[arg(long, value_enum, default_value_t = CollisionPolicy::Rename)]
+        on_collision: CollisionPolicy,
+        
+        /// Output directory, created if missing (default: the user config's out_dir, then out/)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Write one entry's raw bytes to stdout, e.g. to pipe into a hex viewer
+    Cat {
+        /// Internal path of any entry; an asset path without extension means its .uasset
+        entry: String,
+    },
+    /// Show where an entry's data lives in the PAK
+    Offsets {
+        entry: String,
+    },
+    /// Write the offsets of every entry to a CSV (or NDJSON) file
+    OffsetsCsv {
+        file: PathBuf,
+    },
+    /// Write the first bytes of every entry as hex, for signature scans
+    Probe {
+        /// Number of leading bytes per entry
+        #[arg(long)]
+        bytes: usize,
+        
+        #[arg(long, default_value = "probe.ndjson")]
+        out: PathBuf,
+    },
+    /// Repack a PAK into numbered parts under a size limit
+    Split {
+        pak: PathBuf,
+        
+        #[arg(long)]
+        max_size: u64,
+    },
+    /// Combine several PAKs into one; later PAKs win when they contain the same entry
+    Merge {
+        #[arg(long)]
+        output: PathBuf,
+        
+        #[arg(required = true, num_args = 2..)]
+        paks: Vec<PathBuf>,
+    },
+    /// Print a PAK's version, mount point, entry count, encryption and sizes
+    Info {
+        pak: PathBuf,
+        
+        /// Print the facts as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare two PAKs: entries only in one of them, and entries whose data differs
+    Diff {
+        pak_a: PathBuf,
+        pak_b: PathBuf,
+        
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Extract a sample of assets, repack them into a scratch PAK and check they read back unchanged
+    Selftest {
+        pak: PathBuf,
+        
+        /// Number of assets to round-trip, spread evenly over the PAK
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u16).range(1..))]
+        sample: u16,
+    },
+    /// Save the AES key (from --key, --keyfile, KEY/.env or the user config) in the
+    /// OS credential store, for --key-source keyring
+    StoreKey,
+    /// Summarize a previous extraction
+    ShowManifest {
+        manifest: PathBuf,
+        
+        /// Print the raw manifest
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check extracted files against the SHA-256 checksums in their manifest
+    VerifyManifest {
+        manifest: PathBuf,
+    },
+    /// Browse the PAKs at a prompt; reached through --interactive
+    #[command(hide = true)]
+    Interactive,
+    /// Time the read, decrypt and decompress phases of extracting one entry
+    #[command(hide = true)]
+    BenchEntry {
+        path: String,
+        
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+    },
+}
+
+impl Cli {
+    /// Filter on the entry's encryption flag (Some(true) = only encrypted)
+    pub fn encryption_filter(&self) -> Option<bool> {
+        match (self.only_encrypted, self.only_plaintext) {
+            (true, _) => Some(true),
+            (false, true) => Some(false),
+            (false, false) => None,
+        }
+    }
+    
+    /// Log level chosen by -v/-q
+    pub fn log_level(&self) -> LevelFilter {
+        match (self.quiet, self.verbose) {
+            (true, _) => LevelFilter::Warn,
+            (false, 0) => LevelFilter::Info,
+            (false, 1) => LevelFilter::Debug,
+            (false, _) => LevelFilter::Trace,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! What the commands read from a container of game files, so they don't depend on
+//! repak directly. Classic PAKs read by repak are the only container so far; IoStore
+//! (`.utoc`/`.ucas`) would be another.
+
+use std::io::{Read, Seek, Write};
+
+use repak::PakReader;
+
+use crate::error::ExtractError;
+
+/// A reader over the container file, as a trait object
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// Entries of an open container. The file itself is read through a separate reader,
+/// so one container can serve several threads, each with its own file handle.
+pub trait Container: Send + Sync {
+    /// Internal path of every entry, relative to the mount point
+    fn files(&self) -> Vec<String>;
+    
+    fn mount_point(&self) -> &str;
+    
+    /// Read, decrypt and decompress a whole entry
+    fn get(&self, path: &str, reader: &mut dyn ReadSeek) -> Result<Vec<u8>, ExtractError>;
+    
+    /// Like `get`, but decoding straight into `writer`
+    fn read_file(&self, path: &str, reader: &mut dyn ReadSeek, writer: &mut dyn Write) -> Result<(), ExtractError>;
+}
+
+impl Container for PakReader {
+    fn files(&self) -> Vec<String> {
+        PakReader::files(self)
+    }
+    
+    fn mount_point(&self) -> &str {
+        PakReader::mount_point(self)
+    }
+    
+    fn get(&self, path: &str, mut reader: &mut dyn ReadSeek) -> Result<Vec<u8>, ExtractError> {
+        PakReader::get(self, path, &mut reader)
+            .map_err(|source| ExtractError::PakRead { path: path.to_string(), storage: None, source })
+    }
+    
+    fn read_file(&self, path: &str, mut reader: &mut dyn ReadSeek, mut writer: &mut dyn Write) -> Result<(), ExtractError> {
+        PakReader::read_file(self, path, &mut reader, &mut writer)
+            .map_err(|source| ExtractError::PakRead { path: path.to_string(), storage: None, source })
+    }
+}
@@ -0,0 +1,83 @@
+//! `diff`: compare the entries of two PAKs, e.g. a mod PAK against the base game.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use aes::Aes256;
+use serde::Serialize;
+
+use crate::error::ExtractError;
+use crate::say;
+use crate::sha256_hex;
+
+#[derive(Serialize)]
+pub struct PakDiff {
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    /// Entries in both PAKs whose data differs
+    pub changed: Vec<ChangedEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ChangedEntry {
+    pub path: String,
+    pub size_a: u64,
+    pub size_b: u64,
+    pub sha256_a: String,
+    pub sha256_b: String,
+}
+
+/// Compare two PAKs by path, then by size and SHA-256 of the entries in both.
+/// Both are opened with the same key.
+pub fn diff_paks(pak_a: &Path, pak_b: &Path, key: &Aes256) -> Result<PakDiff, ExtractError> {
+    let (a, mut reader_a) = crate::open_pak(pak_a, key)?;
+    let (b, mut reader_b) = crate::open_pak(pak_b, key)?;
+    let files_a: BTreeSet<String> = a.files().into_iter().collect();
+    let files_b: BTreeSet<String> = b.files().into_iter().collect();
+    
+    let mut changed = Vec::new();
+    for path in files_a.intersection(&files_b) {
+        let data_a = a.get(path, &mut reader_a)
+            .map_err(|source| ExtractError::PakRead { path: path.clone(), storage: None, source })
+            .map_err(|e| crate::describe_read_error(e, &mut reader_a, key))?;
+        let data_b = b.get(path, &mut reader_b)
+            .map_err(|source| ExtractError::PakRead { path: path.clone(), storage: None, source })
+            .map_err(|e| crate::describe_read_error(e, &mut reader_b, key))?;
+        if data_a != data_b {
+            changed.push(ChangedEntry {
+                path: path.clone(),
+                size_a: data_a.len() as u64,
+                size_b: data_b.len() as u64,
+                sha256_a: sha256_hex(&data_a),
+                sha256_b: sha256_hex(&data_b),
+            });
+        }
+    }
+    
+    Ok(PakDiff {
+        only_in_a: files_a.difference(&files_b).cloned().collect(),
+        only_in_b: files_b.difference(&files_a).cloned().collect(),
+        changed,
+    })
+}
+
+impl PakDiff {
+    pub fn print(&self, pak_a: &Path, pak_b: &Path) {
+        say!("=== Only in {} ({}) ===", pak_a.display(), self.only_in_a.len());
+        for path in &self.only_in_a {
+            say!("  {}", path);
+        }
+        say!("\n=== Only in {} ({}) ===", pak_b.display(), self.only_in_b.len());
+        for path in &self.only_in_b {
+            say!("  {}", path);
+        }
+        say!("\n=== Changed ({}) ===", self.changed.len());
+        for entry in &self.changed {
+            if entry.size_a == entry.size_b {
+                say!("  {} ({} bytes, SHA-256 {:.12} -> {:.12})", entry.path, entry.size_a, entry.sha256_a, entry.sha256_b);
+            } else {
+                say!("  {} ({} -> {} bytes)", entry.path, entry.size_a, entry.size_b);
+            }
+        }
+    }
+}
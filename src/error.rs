@@ -0,0 +1,105 @@
+//! Errors that callers (and CI pipelines, through the exit code) need to tell apart.
+
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::index::EntryInfo;
+
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    /// No AES key in the environment, `.env` or the user config
+    #[error("No AES key: set {env_var} (or .env) or \"key\" in the user config")]
+    MissingKey { env_var: String },
+    #[error("AES key is not valid hex: {0}")]
+    InvalidKeyHex(#[source] hex::FromHexError),
+    #[error("AES key must be 32 bytes (64 hex characters), got {got} bytes")]
+    KeyWrongLength { got: usize },
+    #[error("Invalid keyfile {}: {reason}", path.display())]
+    InvalidKeyfile { path: PathBuf, reason: String },
+    /// The OS credential store could not be read or written (`--key-source keyring`, `store-key`)
+    #[error("OS credential store: {0}")]
+    Keyring(#[source] keyring::Error),
+    #[error("PAK file not found: {} (pass --pak <path>)", .0.display())]
+    PakNotFound(PathBuf),
+    /// The PAK's index is encrypted and does not decrypt to anything sensible with the key
+    #[error("AES key appears incorrect for this PAK: {} (its index does not decrypt; check --key, --keyfile or KEY)", .0.display())]
+    WrongKey(PathBuf),
+    /// An IoStore container (`.utoc`/`.ucas`), which neither repak nor our index parser reads
+    #[error("{} is an IoStore container, which is not supported; open the .pak next to it, or extract IoStore content with a tool such as retoc or FModel", .0.display())]
+    IoStoreUnsupported(PathBuf),
+    #[error("Failed to open PAK: {0}")]
+    PakOpen(#[source] repak::Error),
+    /// An entry that is in the PAK but could not be read (decryption or decompression).
+    /// `storage` describes the entry when its index record was looked up, see `with_entry`.
+    #[error("Failed to read {path}{}: {source}", storage.as_ref().map_or(String::new(), |s| format!(" ({})", s)))]
+    PakRead { path: String, storage: Option<String>, source: repak::Error },
+    #[error("Entry not found in PAK: {0}")]
+    AssetNotFound(String),
+    /// An entry path that would be written outside the output directory
+    #[error("Refusing to extract entry with unsafe path: {0}")]
+    UnsafeEntryPath(String),
+    /// Extraction finished, but some assets or entries were not extracted
+    #[error("{failed} failed to extract; pass --ignore-errors to exit successfully anyway")]
+    PartialFailure { failed: usize },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl ExtractError {
+    /// Add how the entry is stored to a `PakRead`: its compression method and whether
+    /// it is encrypted, which tells a missing decompressor from a key or data problem
+    pub fn with_entry(self, entry: Option<&EntryInfo>) -> Self {
+        match (self, entry) {
+            (ExtractError::PakRead { path, storage: None, source }, Some(entry)) => {
+                let mut storage = entry.storage();
+                if entry.compression.as_deref() == Some("Oodle") && !cfg!(feature = "oodle") {
+                    storage.push_str("; this build can't decompress Oodle, rebuild with --features oodle");
+                }
+                ExtractError::PakRead { path, storage: Some(storage), source }
+            }
+            (err, _) => err,
+        }
+    }
+    
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ExtractError::AssetNotFound(_) => 2,
+            ExtractError::MissingKey { .. }
+            | ExtractError::InvalidKeyHex(_)
+            | ExtractError::KeyWrongLength { .. }
+            | ExtractError::InvalidKeyfile { .. }
+            | ExtractError::WrongKey(_)
+            | ExtractError::Keyring(_) => 3,
+            ExtractError::PakNotFound(_) | ExtractError::Io(_) => 4,
+            ExtractError::UnsafeEntryPath(_) | ExtractError::IoStoreUnsupported(_) => 1,
+            ExtractError::PakOpen(e) | ExtractError::PakRead { source: e, .. } => {
+                if caused_by_io(e) { 4 } else { 1 }
+            }
+            ExtractError::PartialFailure { .. } => 5,
+        }
+    }
+}
+
+/// Process exit code for an error returned by the extractor:
+/// 2 asset not found, 3 key errors, 4 I/O errors, 5 partial batch failure, 1 anything else
+pub fn exit_code(err: &(dyn std::error::Error + 'static)) -> u8 {
+    match err.downcast_ref::<ExtractError>() {
+        Some(e) => e.exit_code(),
+        None if caused_by_io(err) => 4,
+        None => 1,
+    }
+}
+
+/// Whether an I/O error is somewhere in the error's source chain
+fn caused_by_io(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(e) = source {
+        if e.is::<io::Error>() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
@@ -0,0 +1,799 @@
+//! Minimal reader for the PAK footer and index.
+//!
+//! repak only exposes the file list, so this walks the index ourselves to get
+//! per-entry metadata (offsets, sizes, compression, encryption) for tooling
+//! that needs to look at the raw bytes.
+
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use aes::Aes256;
+use aes::cipher::BlockDecrypt;
+use byteorder::{LE, ReadBytesExt};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use repak::Version;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+const PAK_MAGIC: u32 = 0x5A6F12E1;
+
+/// (version, footer size, position of the magic within the footer)
+const FOOTER_LAYOUTS: [(Version, u64, u64); 9] = [
+    (Version::V11, 221, 17),
+    (Version::V10, 221, 17),
+    (Version::V9, 222, 17),
+    (Version::V8B, 221, 17),
+    (Version::V8A, 189, 17),
+    (Version::V7, 61, 17),
+    (Version::V6, 45, 1),
+    (Version::V5, 45, 1),
+    (Version::V4, 45, 1),
+];
+
+pub struct PakIndex {
+    pub version: Version,
+    pub entries: Vec<EntryInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EntryInfo {
+    pub path: String,
+    /// Absolute offset of the entry's header record in the PAK
+    pub offset: u64,
+    /// Absolute offset of the first byte of entry data (just after the header record)
+    pub data_offset: u64,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub compression: Option<String>,
+    pub encrypted: bool,
+    /// Absolute (start, end) byte ranges of each compression block
+    pub blocks: Vec<(u64, u64)>,
+}
+
+impl EntryInfo {
+    /// Number of bytes the entry data occupies on disk (encrypted data is padded to 16 bytes)
+    pub fn stored_size(&self) -> u64 {
+        match self.blocks.last() {
+            Some(&(start, end)) if self.encrypted => start + align16(end - start) - self.data_offset,
+            Some(&(_, end)) => end - self.data_offset,
+            None if self.encrypted => align16(self.compressed_size),
+            None => self.compressed_size,
+        }
+    }
+    
+    pub fn compression_name(&self) -> &str {
+        self.compression.as_deref().unwrap_or("None")
+    }
+    
+    /// How the data is stored, e.g. `Oodle, encrypted`
+    pub fn storage(&self) -> String {
+        format!("{}, {}", self.compression_name(), if self.encrypted { "encrypted" } else { "not encrypted" })
+    }
+}
+
+impl PakIndex {
+    pub fn find(&self, path: &str) -> Option<&EntryInfo> {
+        self.entries
+            .binary_search_by(|e| e.path.as_str().cmp(path))
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+}
+
+/// SHA-1 stored in the entry's header record, or `None` if the PAK didn't record one
+pub fn stored_hash<R: Read + Seek>(reader: &mut R, version: Version, entry: &EntryInfo) -> io::Result<Option<[u8; 20]>> {
+    // offset, compressed size, uncompressed size, compression method
+    let compression_size = if version == Version::V8A { 1 } else { 4 };
+    reader.seek(SeekFrom::Start(entry.offset + 8 + 8 + 8 + compression_size))?;
+    let mut hash = [0u8; 20];
+    reader.read_exact(&mut hash)?;
+    Ok(if hash == [0u8; 20] { None } else { Some(hash) })
+}
+
+/// SHA-1 of the entry data exactly as stored (still compressed and encrypted),
+/// which is what UE hashes when writing the PAK
+pub fn data_hash<R: Read + Seek>(reader: &mut R, entry: &EntryInfo) -> io::Result<[u8; 20]> {
+    reader.seek(SeekFrom::Start(entry.data_offset))?;
+    let mut hasher = Sha1::new();
+    io::copy(&mut reader.take(entry.stored_size()), &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+struct Footer {
+    version: Version,
+    encrypted: bool,
+    index_offset: u64,
+    index_size: u64,
+    compression: Vec<String>,
+}
+
+/// PAK version from the footer magic alone, without reading the index, so it
+/// can be reported even for PAKs that fail to open (e.g. with the wrong key)
+pub fn detect_version<R: Read + Seek>(reader: &mut R) -> io::Result<Version> {
+    Ok(read_footer(reader)?.version)
+}
+
+/// Whether `key` decrypts the PAK's index, judged by the index starting with a
+/// plausible mount point. Always true when the index is not encrypted.
+pub fn key_decrypts_index<R: Read + Seek>(reader: &mut R, key: &Aes256) -> io::Result<bool> {
+    let footer = read_footer(reader)?;
+    if !footer.encrypted {
+        return Ok(true);
+    }
+    if footer.index_size < 16 {
+        return Ok(false);
+    }
+    
+    // The mount point is the first thing in the index: a length, then the
+    // string, which for a real mount point is printable text
+    let block = read_block(reader, footer.index_offset, 16, true, Some(key))?;
+    let len = i32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+    let printable = |c: u8| c == 0 || c == b' ' || c.is_ascii_graphic();
+    Ok(match len {
+        1..=1024 => block[4..].iter().take(len as usize).all(|&c| printable(c)),
+        -1024..=-1 => block[4..].chunks(2).take(-len as usize).all(|c| c[1] == 0 && printable(c[0])),
+        _ => false,
+    })
+}
+
+/// Parse the footer and index of a PAK. `key` is only needed when the index is encrypted.
+pub fn read_index<R: Read + Seek>(reader: &mut R, key: Option<&Aes256>) -> io::Result<PakIndex> {
+    let footer = read_footer(reader)?;
+    
+    let index = read_block(reader, footer.index_offset, footer.index_size, footer.encrypted, key)?;
+    let mut index = Cursor::new(index);
+    
+    // Mount point
+    read_string(&mut index)?;
+    let entry_count = index.read_u32::<LE>()?;
+    
+    let mut entries = Vec::with_capacity(entry_count as usize);
+    
+    if major(footer.version) >= 10 {
+        let encoded = read_encoded_index(&mut index, &footer)?;
+        
+        let (offset, size) = encoded.full_dir_index.ok_or_else(|| {
+            invalid("PAK has no full directory index (use --by-hash for path-hash-only PAKs)")
+        })?;
+        let dir_index = read_block(reader, offset, size, footer.encrypted, key)?;
+        let mut dir_index = Cursor::new(dir_index);
+        
+        let dir_count = dir_index.read_u32::<LE>()?;
+        for _ in 0..dir_count {
+            let dir_name = read_string(&mut dir_index)?;
+            let dir_name = dir_name.trim_start_matches('/');
+            let file_count = dir_index.read_u32::<LE>()?;
+            for _ in 0..file_count {
+                let file_name = read_string(&mut dir_index)?;
+                let location = dir_index.read_i32::<LE>()?;
+                let path = format!("{}{}", dir_name, file_name);
+                
+                if let Some(raw) = encoded.entry(location, &footer)? {
+                    entries.push(raw.into_info(path, &footer));
+                }
+            }
+        }
+    } else {
+        for _ in 0..entry_count {
+            let path = read_string(&mut index)?;
+            let raw = read_entry(&mut index, &footer)?;
+            entries.push(raw.into_info(path, &footer));
+        }
+    }
+    
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    
+    Ok(PakIndex { version: footer.version, entries })
+}
+
+/// Entries of a V10+ PAK keyed by their 64-bit path hash, for PAKs without a
+/// full directory index. Paths are unknown, so each entry's `path` is the hex hash.
+pub fn read_hashed_entries<R: Read + Seek>(reader: &mut R, key: Option<&Aes256>) -> io::Result<Vec<(u64, EntryInfo)>> {
+    let footer = read_footer(reader)?;
+    if major(footer.version) < 10 {
+        return Err(invalid("PAKs before V10 have no path hash index"));
+    }
+    
+    let index = read_block(reader, footer.index_offset, footer.index_size, footer.encrypted, key)?;
+    let mut index = Cursor::new(index);
+    // Mount point, entry count
+    read_string(&mut index)?;
+    index.read_u32::<LE>()?;
+    let encoded = read_encoded_index(&mut index, &footer)?;
+    
+    let (offset, size) = encoded.path_hash_index
+        .ok_or_else(|| invalid("PAK has no path hash index"))?;
+    let hash_index = read_block(reader, offset, size, footer.encrypted, key)?;
+    let mut hash_index = Cursor::new(hash_index);
+    
+    let count = hash_index.read_u32::<LE>()?;
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let hash = hash_index.read_u64::<LE>()?;
+        let location = hash_index.read_i32::<LE>()?;
+        if let Some(raw) = encoded.entry(location, &footer)? {
+            entries.push((hash, raw.into_info(format!("{:016x}", hash), &footer)));
+        }
+    }
+    
+    Ok(entries)
+}
+
+/// Read, decrypt and decompress an entry's data without going through repak
+/// (which needs the entry's path). Supports Zlib and Gzip compression.
+pub fn read_entry_data<R: Read + Seek>(reader: &mut R, entry: &EntryInfo, key: Option<&Aes256>) -> io::Result<Vec<u8>> {
+    let mut blocks = read_stored_blocks(reader, entry)?;
+    if entry.encrypted {
+        let key = key.ok_or_else(|| invalid("entry is encrypted but no key was given"))?;
+        decrypt_blocks(key, entry, &mut blocks);
+    }
+    
+    let Some(method) = &entry.compression else {
+        return Ok(blocks.concat());
+    };
+    
+    let mut data = Vec::with_capacity(entry.uncompressed_size as usize);
+    for block in &blocks {
+        decompress_block(method, block, &mut data)?;
+    }
+    Ok(data)
+}
+
+/// The first `len` bytes of an entry's data. Only the blocks needed to produce
+/// them are read and decompressed. Supports Zlib and Gzip compression.
+pub fn read_entry_prefix<R: Read + Seek>(reader: &mut R, entry: &EntryInfo, key: Option<&Aes256>, len: usize) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(len);
+    for (start, stored_len) in stored_ranges(entry) {
+        if data.len() >= len {
+            break;
+        }
+        
+        // Uncompressed data can be cut short, compressed blocks must be read whole
+        let wanted = match entry.compression {
+            Some(_) => stored_len,
+            None => stored_len.min(len as u64),
+        };
+        let mut block = vec![0u8; if entry.encrypted { align16(wanted) } else { wanted } as usize];
+        reader.seek(SeekFrom::Start(start))?;
+        reader.read_exact(&mut block)?;
+        if entry.encrypted {
+            let key = key.ok_or_else(|| invalid("entry is encrypted but no key was given"))?;
+            decrypt(key, &mut block);
+            block.truncate(wanted as usize);
+        }
+        
+        match &entry.compression {
+            Some(method) => decompress_block(method, &block, &mut data)?,
+            None => data.extend_from_slice(&block),
+        }
+    }
+    data.truncate(len);
+    Ok(data)
+}
+
+fn decompress_block(method: &str, block: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+    match method.to_lowercase().as_str() {
+        "zlib" => ZlibDecoder::new(block).read_to_end(out)?,
+        "gzip" => GzDecoder::new(block).read_to_end(out)?,
+        _ => return Err(invalid(&format!("{} compression is not supported here", method))),
+    };
+    Ok(())
+}
+
+/// The stored bytes of an entry as they are on disk: one buffer per compression
+/// block, or a single buffer for uncompressed entries. Encrypted data is still
+/// encrypted and padded to the AES block size.
+pub fn read_stored_blocks<R: Read + Seek>(reader: &mut R, entry: &EntryInfo) -> io::Result<Vec<Vec<u8>>> {
+    stored_ranges(entry)
+        .map(|(start, len)| {
+            let stored_len = if entry.encrypted { align16(len) } else { len };
+            let mut data = vec![0u8; stored_len as usize];
+            reader.seek(SeekFrom::Start(start))?;
+            reader.read_exact(&mut data)?;
+            Ok(data)
+        })
+        .collect()
+}
+
+/// Decrypt blocks from `read_stored_blocks` in place and strip the padding
+pub fn decrypt_blocks(key: &Aes256, entry: &EntryInfo, blocks: &mut [Vec<u8>]) {
+    for (block, (_, len)) in blocks.iter_mut().zip(stored_ranges(entry)) {
+        decrypt(key, block);
+        block.truncate(len as usize);
+    }
+}
+
+/// (start, unpadded length) of each stored block of an entry
+fn stored_ranges(entry: &EntryInfo) -> Box<dyn Iterator<Item = (u64, u64)> + '_> {
+    if entry.compression.is_some() {
+        Box::new(entry.blocks.iter().map(|&(start, end)| (start, end - start)))
+    } else {
+        Box::new(std::iter::once((entry.data_offset, entry.uncompressed_size)))
+    }
+}
+
+/// Locations of the V10+ index sections plus the entries they point into
+struct EncodedIndex {
+    path_hash_index: Option<(u64, u64)>,
+    full_dir_index: Option<(u64, u64)>,
+    encoded: Vec<u8>,
+    unencoded: Vec<RawEntry>,
+}
+
+impl EncodedIndex {
+    /// Resolve an entry location: an offset into the encoded entries, or
+    /// (when negative) an index into the unencoded list
+    fn entry(&self, location: i32, footer: &Footer) -> io::Result<Option<RawEntry>> {
+        if location >= 0 {
+            let mut cursor = Cursor::new(&self.encoded[..]);
+            cursor.set_position(location as u64);
+            read_encoded_entry(&mut cursor, footer).map(Some)
+        } else {
+            let list_index = (-(location as i64) - 1) as usize;
+            Ok(self.unencoded.get(list_index).cloned())
+        }
+    }
+}
+
+/// Read the V10+ primary index after the mount point and entry count
+fn read_encoded_index<R: Read + Seek>(index: &mut R, footer: &Footer) -> io::Result<EncodedIndex> {
+    // Path hash seed
+    index.read_u64::<LE>()?;
+    
+    let read_section = |index: &mut R| -> io::Result<Option<(u64, u64)>> {
+        if index.read_u32::<LE>()? == 0 {
+            return Ok(None);
+        }
+        let offset = index.read_u64::<LE>()?;
+        let size = index.read_u64::<LE>()?;
+        index.seek(SeekFrom::Current(20))?;
+        Ok(Some((offset, size)))
+    };
+    let path_hash_index = read_section(index)?;
+    let full_dir_index = read_section(index)?;
+    
+    let encoded_size = index.read_u32::<LE>()? as usize;
+    let mut encoded = vec![0u8; encoded_size];
+    index.read_exact(&mut encoded)?;
+    
+    let unencoded_count = index.read_u32::<LE>()?;
+    let mut unencoded = Vec::with_capacity(unencoded_count as usize);
+    for _ in 0..unencoded_count {
+        unencoded.push(read_entry(index, footer)?);
+    }
+    
+    Ok(EncodedIndex { path_hash_index, full_dir_index, encoded, unencoded })
+}
+
+fn read_footer<R: Read + Seek>(reader: &mut R) -> io::Result<Footer> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    
+    for (version, size, magic_pos) in FOOTER_LAYOUTS {
+        if file_len < size {
+            continue;
+        }
+        reader.seek(SeekFrom::Start(file_len - size + magic_pos))?;
+        if reader.read_u32::<LE>()? != PAK_MAGIC || reader.read_u32::<LE>()? != major(version) {
+            continue;
+        }
+        
+        // Encrypted flag sits right before the magic
+        reader.seek(SeekFrom::Start(file_len - size + magic_pos - 1))?;
+        let encrypted = reader.read_u8()? != 0;
+        reader.seek(SeekFrom::Current(8))?;
+        let index_offset = reader.read_u64::<LE>()?;
+        let index_size = reader.read_u64::<LE>()?;
+        reader.seek(SeekFrom::Current(20))?;
+        if version == Version::V9 {
+            reader.seek(SeekFrom::Current(1))?;
+        }
+        
+        let name_count = match version {
+            Version::V8A => 4,
+            v if major(v) >= 8 => 5,
+            _ => 0,
+        };
+        let mut compression = Vec::new();
+        for _ in 0..name_count {
+            let mut name = [0u8; 32];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8_lossy(&name).trim_end_matches('\0').to_string();
+            if !name.is_empty() {
+                compression.push(name);
+            }
+        }
+        
+        return Ok(Footer { version, encrypted, index_offset, index_size, compression });
+    }
+    
+    Err(invalid("no PAK footer found (unsupported version or not a PAK file)"))
+}
+
+fn read_block<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    size: u64,
+    encrypted: bool,
+    key: Option<&Aes256>,
+) -> io::Result<Vec<u8>> {
+    let mut data = vec![0u8; size as usize];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut data)?;
+    
+    if encrypted {
+        let key = key.ok_or_else(|| invalid("PAK index is encrypted but no key was given"))?;
+        decrypt(key, &mut data);
+    }
+    
+    Ok(data)
+}
+
+pub fn decrypt(key: &Aes256, data: &mut [u8]) {
+    for chunk in data.chunks_exact_mut(16) {
+        key.decrypt_block(aes::Block::from_mut_slice(chunk));
+    }
+}
+
+#[derive(Clone)]
+struct RawEntry {
+    offset: u64,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    /// 1-based index into the footer compression names (0 = none)
+    compression: u32,
+    encrypted: bool,
+    /// (start, end) of each block relative to `offset` (or absolute before V5)
+    blocks: Vec<(u64, u64)>,
+}
+
+impl RawEntry {
+    fn into_info(self, path: String, footer: &Footer) -> EntryInfo {
+        let header_size = serialized_size(footer.version, self.compression, self.blocks.len() as u64);
+        let base = if major(footer.version) >= 5 { self.offset } else { 0 };
+        let compression = compression_name(footer, self.compression);
+        
+        EntryInfo {
+            path,
+            offset: self.offset,
+            data_offset: self.offset + header_size,
+            compressed_size: self.compressed_size,
+            uncompressed_size: self.uncompressed_size,
+            compression,
+            encrypted: self.encrypted,
+            blocks: self.blocks.iter().map(|(start, end)| (base + start, base + end)).collect(),
+        }
+    }
+}
+
+fn compression_name(footer: &Footer, index: u32) -> Option<String> {
+    if index == 0 {
+        return None;
+    }
+    if major(footer.version) >= 8 {
+        return Some(
+            footer.compression
+                .get(index as usize - 1)
+                .cloned()
+                .unwrap_or_else(|| format!("Unknown({})", index)),
+        );
+    }
+    // Before V8 the field is a method flag rather than an index
+    Some(match index {
+        1 => "Zlib".to_string(),
+        2 => "Gzip".to_string(),
+        4 => "Custom".to_string(),
+        n => format!("Unknown({})", n),
+    })
+}
+
+fn read_entry<R: Read + Seek>(reader: &mut R, footer: &Footer) -> io::Result<RawEntry> {
+    let offset = reader.read_u64::<LE>()?;
+    let compressed_size = reader.read_u64::<LE>()?;
+    let uncompressed_size = reader.read_u64::<LE>()?;
+    let compression = if footer.version == Version::V8A {
+        reader.read_u8()? as u32
+    } else {
+        reader.read_u32::<LE>()?
+    };
+    // SHA-1 hash
+    reader.seek(SeekFrom::Current(20))?;
+    
+    let mut blocks = Vec::new();
+    if compression != 0 {
+        let count = reader.read_u32::<LE>()?;
+        for _ in 0..count {
+            let start = reader.read_u64::<LE>()?;
+            let end = reader.read_u64::<LE>()?;
+            blocks.push((start, end));
+        }
+    }
+    let encrypted = reader.read_u8()? & 1 != 0;
+    // Compression block size
+    reader.read_u32::<LE>()?;
+    
+    Ok(RawEntry { offset, compressed_size, uncompressed_size, compression, encrypted, blocks })
+}
+
+fn read_encoded_entry<R: Read>(reader: &mut R, footer: &Footer) -> io::Result<RawEntry> {
+    let bits = reader.read_u32::<LE>()?;
+    
+    let compression = (bits >> 23) & 0x3f;
+    let encrypted = bits & (1 << 22) != 0;
+    let block_count = (bits >> 6) & 0xffff;
+    if bits & 0x3f == 0x3f {
+        // Compression block size that doesn't fit the packed field
+        reader.read_u32::<LE>()?;
+    }
+    
+    let mut var_int = |bit: u32| -> io::Result<u64> {
+        if bits & (1 << bit) != 0 {
+            Ok(reader.read_u32::<LE>()? as u64)
+        } else {
+            reader.read_u64::<LE>()
+        }
+    };
+    
+    let offset = var_int(31)?;
+    let uncompressed_size = var_int(30)?;
+    let compressed_size = if compression != 0 { var_int(29)? } else { uncompressed_size };
+    
+    let header_size = serialized_size(footer.version, compression, block_count as u64);
+    let mut blocks = Vec::new();
+    if block_count == 1 && !encrypted {
+        blocks.push((header_size, header_size + compressed_size));
+    } else if block_count > 0 {
+        let mut start = header_size;
+        for _ in 0..block_count {
+            let size = reader.read_u32::<LE>()? as u64;
+            blocks.push((start, start + size));
+            start += if encrypted { align16(size) } else { size };
+        }
+    }
+    
+    Ok(RawEntry { offset, compressed_size, uncompressed_size, compression, encrypted, blocks })
+}
+
+/// Size of the entry header record that precedes the data in the PAK
+fn serialized_size(version: Version, compression: u32, block_count: u64) -> u64 {
+    let mut size = 8 + 8 + 8 + 20 + 1;
+    size += if version == Version::V8A { 1 } else { 4 };
+    if compression != 0 {
+        size += 4 + 16 * block_count;
+    }
+    if major(version) >= 3 {
+        size += 4;
+    }
+    size
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = reader.read_i32::<LE>()?;
+    if len >= 0 {
+        let mut buf = vec![0u8; len as usize];
+        reader.read_exact(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).trim_end_matches('\0').to_string())
+    } else {
+        let mut buf = Vec::with_capacity(-len as usize);
+        for _ in 0..-len {
+            buf.push(reader.read_u16::<LE>()?);
+        }
+        Ok(String::from_utf16_lossy(&buf).trim_end_matches('\0').to_string())
+    }
+}
+
+fn major(version: Version) -> u32 {
+    match version {
+        Version::V0 => 0,
+        Version::V1 => 1,
+        Version::V2 => 2,
+        Version::V3 => 3,
+        Version::V4 => 4,
+        Version::V5 => 5,
+        Version::V6 => 6,
+        Version::V7 => 7,
+        Version::V8A | Version::V8B => 8,
+        Version::V9 => 9,
+        Version::V10 => 10,
+        Version::V11 => 11,
+    }
+}
+
+fn align16(n: u64) -> u64 {
+    (n + 15) & !15
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+    
+    /// Footer as UE writes it for `version`, after some entry data
+    fn pak_with_footer(version: Version, encrypted: bool, index_offset: u64, index_size: u64, names: &[&str]) -> Vec<u8> {
+        let (_, size, magic_pos) = FOOTER_LAYOUTS.iter().find(|(v, _, _)| *v == version).copied().unwrap();
+        let mut pak = vec![0u8; 300];
+        if magic_pos == 17 {
+            // Encryption key GUID
+            pak.extend([0xAB; 16]);
+        }
+        pak.push(encrypted as u8);
+        pak.write_u32::<LE>(PAK_MAGIC).unwrap();
+        pak.write_u32::<LE>(major(version)).unwrap();
+        pak.write_u64::<LE>(index_offset).unwrap();
+        pak.write_u64::<LE>(index_size).unwrap();
+        pak.extend([0xCD; 20]);
+        if version == Version::V9 {
+            // Frozen index flag
+            pak.push(0);
+        }
+        let name_count = match version {
+            Version::V8A => 4,
+            v if major(v) >= 8 => 5,
+            _ => 0,
+        };
+        for i in 0..name_count {
+            let mut name = [0u8; 32];
+            if let Some(n) = names.get(i) {
+                name[..n.len()].copy_from_slice(n.as_bytes());
+            }
+            pak.extend(name);
+        }
+        assert_eq!(pak.len() as u64, 300 + size);
+        pak
+    }
+    
+    fn test_footer(version: Version, compression: &[&str]) -> Footer {
+        Footer {
+            version,
+            encrypted: false,
+            index_offset: 0,
+            index_size: 0,
+            compression: compression.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+    
+    #[test]
+    fn footer_is_found_for_every_version() {
+        for (version, _, _) in FOOTER_LAYOUTS {
+            let pak = pak_with_footer(version, version == Version::V11, 0x1234, 0x56, &["Zlib", "Oodle"]);
+            let footer = read_footer(&mut Cursor::new(pak)).unwrap();
+            assert_eq!(footer.version, version);
+            assert_eq!(footer.encrypted, version == Version::V11);
+            assert_eq!((footer.index_offset, footer.index_size), (0x1234, 0x56));
+            let names: &[&str] = if major(version) >= 8 { &["Zlib", "Oodle"] } else { &[] };
+            assert_eq!(footer.compression, names);
+        }
+    }
+    
+    #[test]
+    fn footer_is_required() {
+        assert!(read_footer(&mut Cursor::new(vec![0u8; 400])).is_err());
+        assert!(read_footer(&mut Cursor::new(vec![0u8; 10])).is_err());
+    }
+    
+    #[test]
+    fn encoded_uncompressed_entry() {
+        let mut record = Vec::new();
+        // 32-bit offset and size, compression block size field 0x10
+        record.write_u32::<LE>((1 << 31) | (1 << 30) | 0x10).unwrap();
+        record.write_u32::<LE>(0x1000).unwrap();
+        record.write_u32::<LE>(500).unwrap();
+        let footer = test_footer(Version::V11, &[]);
+        let entry = read_encoded_entry(&mut Cursor::new(record), &footer).unwrap()
+            .into_info("a.uasset".to_string(), &footer);
+        
+        assert_eq!(entry.offset, 0x1000);
+        assert_eq!(entry.data_offset, 0x1000 + 8 + 8 + 8 + 20 + 1 + 4 + 4);
+        assert_eq!((entry.compressed_size, entry.uncompressed_size), (500, 500));
+        assert_eq!(entry.compression, None);
+        assert!(!entry.encrypted && entry.blocks.is_empty());
+        assert_eq!(entry.stored_size(), 500);
+    }
+    
+    #[test]
+    fn encoded_single_block_entry() {
+        let mut record = Vec::new();
+        // Compression method 1, one block, 64-bit offset, 32-bit sizes
+        record.write_u32::<LE>((1 << 23) | (1 << 6) | (1 << 30) | (1 << 29)).unwrap();
+        record.write_u64::<LE>(5_000_000_000).unwrap();
+        record.write_u32::<LE>(1000).unwrap();
+        record.write_u32::<LE>(400).unwrap();
+        let footer = test_footer(Version::V11, &["Zlib"]);
+        let entry = read_encoded_entry(&mut Cursor::new(record), &footer).unwrap()
+            .into_info("a.uexp".to_string(), &footer);
+        
+        // The only block starts right after the header, so its size isn't stored
+        let header = 8 + 8 + 8 + 20 + 1 + 4 + (4 + 16) + 4;
+        assert_eq!(entry.data_offset, 5_000_000_000 + header);
+        assert_eq!(entry.blocks, [(5_000_000_000 + header, 5_000_000_000 + header + 400)]);
+        assert_eq!(entry.compression.as_deref(), Some("Zlib"));
+        assert_eq!((entry.compressed_size, entry.uncompressed_size), (400, 1000));
+        assert_eq!(entry.stored_size(), 400);
+    }
+    
+    #[test]
+    fn encoded_encrypted_blocks_are_padded() {
+        let mut record = Vec::new();
+        // Compression method 2, encrypted, two blocks, all 32-bit, block size stored separately
+        record.write_u32::<LE>((2 << 23) | (1 << 22) | (2 << 6) | (1 << 31) | (1 << 30) | (1 << 29) | 0x3f).unwrap();
+        record.write_u32::<LE>(0x40000).unwrap();
+        record.write_u32::<LE>(0x2000).unwrap();
+        record.write_u32::<LE>(300).unwrap();
+        record.write_u32::<LE>(150).unwrap();
+        record.write_u32::<LE>(100).unwrap();
+        record.write_u32::<LE>(50).unwrap();
+        let footer = test_footer(Version::V11, &["Zlib", "Oodle"]);
+        let entry = read_encoded_entry(&mut Cursor::new(record), &footer).unwrap()
+            .into_info("a.ubulk".to_string(), &footer);
+        
+        let header = 8 + 8 + 8 + 20 + 1 + 4 + (4 + 2 * 16) + 4;
+        let start = 0x2000 + header;
+        // Each block starts at the 16-byte padded end of the previous one
+        assert_eq!(entry.blocks, [(start, start + 100), (start + 112, start + 162)]);
+        assert_eq!(entry.compression.as_deref(), Some("Oodle"));
+        assert!(entry.encrypted);
+        assert_eq!(entry.stored_size(), 112 + 64);
+    }
+    
+    /// Full entry record as written before V10 (and for unencoded V10+ entries)
+    fn entry_record(version: Version, block: (u64, u64)) -> Vec<u8> {
+        let mut record = Vec::new();
+        record.write_u64::<LE>(0x500).unwrap();
+        record.write_u64::<LE>(64).unwrap();
+        record.write_u64::<LE>(128).unwrap();
+        // Compression method 1; V8A stores it in a single byte
+        if version == Version::V8A {
+            record.write_u8(1).unwrap();
+        } else {
+            record.write_u32::<LE>(1).unwrap();
+        }
+        record.extend([0; 20]);
+        record.write_u32::<LE>(1).unwrap();
+        record.write_u64::<LE>(block.0).unwrap();
+        record.write_u64::<LE>(block.1).unwrap();
+        record.write_u8(0).unwrap();
+        record.write_u32::<LE>(0x10000).unwrap();
+        record
+    }
+    
+    #[test]
+    fn entry_blocks_are_relative_from_v5() {
+        let footer = test_footer(Version::V8A, &["Zlib"]);
+        let entry = read_entry(&mut Cursor::new(entry_record(Version::V8A, (0x46, 0x86))), &footer).unwrap()
+            .into_info("a.uasset".to_string(), &footer);
+        // The first block starts right after the header record
+        let header = 8 + 8 + 8 + 20 + 1 + 1 + (4 + 16) + 4;
+        assert_eq!(header, 0x46);
+        assert_eq!(entry.data_offset, 0x500 + header);
+        assert_eq!(entry.blocks, [(0x500 + 0x46, 0x500 + 0x86)]);
+        assert_eq!(entry.compression.as_deref(), Some("Zlib"));
+        assert_eq!(entry.stored_size(), 64);
+        
+        // Before V5 they are absolute, and the method is a flag rather than a name index
+        let footer = test_footer(Version::V4, &[]);
+        let entry = read_entry(&mut Cursor::new(entry_record(Version::V4, (0x549, 0x589))), &footer).unwrap()
+            .into_info("a.uasset".to_string(), &footer);
+        assert_eq!(entry.blocks, [(0x549, 0x589)]);
+        assert_eq!(entry.compression.as_deref(), Some("Zlib"));
+        assert_eq!(entry.stored_size(), 64);
+    }
+    
+    #[test]
+    fn stored_size_without_blocks() {
+        let mut entry = EntryInfo {
+            path: "a.uasset".to_string(),
+            offset: 0,
+            data_offset: 53,
+            compressed_size: 100,
+            uncompressed_size: 100,
+            compression: None,
+            encrypted: false,
+            blocks: Vec::new(),
+        };
+        assert_eq!(entry.stored_size(), 100);
+        entry.encrypted = true;
+        assert_eq!(entry.stored_size(), 112);
+    }
+}
@@ -0,0 +1,132 @@
+//! On-disk cache of each PAK's parsed index, so `list`, `search`, `stats` and `tree`
+//! don't re-read a multi-GB PAK's index on every run. A PAK's cache file is named
+//! after its canonical path and is rebuilt whenever the PAK's size or mtime changes.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use aes::Aes256;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::ExtractError;
+use crate::index::{self, EntryInfo};
+
+/// Bumped when the cache layout or `EntryInfo` changes, which invalidates old caches
+const CACHE_VERSION: u32 = 1;
+
+/// Everything `list`, `search`, `stats` and `tree` need, layered across PAKs like `PakSet`
+pub struct Listing {
+    /// Every internal path, in the order first seen
+    pub files: Vec<String>,
+    /// Index entry of every internal path, from the PAK that provides it
+    pub entries: HashMap<String, EntryInfo>,
+}
+
+/// One PAK's cached index
+#[derive(Serialize, Deserialize)]
+struct CachedIndex {
+    version: u32,
+    pak: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    /// Internal paths in PAK order, as repak lists them
+    files: Vec<String>,
+    entries: Vec<EntryInfo>,
+}
+
+/// Directory holding the cache files (`~/.cache/mt-pak-extract` on Linux)
+pub fn cache_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "mt-pak-extract").map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+/// The index of every PAK in load order, from the cache where it is current.
+/// Without `use_cache` every index is parsed and the cache is left alone.
+pub fn load(paths: &[PathBuf], key: &Aes256, use_cache: bool) -> Result<Listing, ExtractError> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let mut entries = HashMap::new();
+    for path in paths {
+        let (pak_files, pak_entries) = if use_cache { load_one(path, key)? } else { parse(path, key)? };
+        for file in pak_files {
+            if seen.insert(file.clone()) {
+                files.push(file);
+            }
+        }
+        // A later PAK's entry shadows an earlier one's
+        for entry in pak_entries {
+            entries.insert(entry.path.clone(), entry);
+        }
+    }
+    Ok(Listing { files, entries })
+}
+
+/// Internal paths in PAK order and index entries of one PAK
+type PakIndexParts = (Vec<String>, Vec<EntryInfo>);
+
+fn load_one(path: &Path, key: &Aes256) -> Result<PakIndexParts, ExtractError> {
+    let pak = fs::canonicalize(path)?;
+    let metadata = fs::metadata(&pak)?;
+    let modified = metadata.modified()?;
+    let Some(cache_path) = cache_dir().map(|dir| dir.join(cache_file_name(&pak))) else {
+        return parse(path, key);
+    };
+    
+    if let Some(cached) = read_cache(&cache_path)
+        && cached.version == CACHE_VERSION
+        && cached.pak == pak
+        && cached.size == metadata.len()
+        && cached.modified == modified
+    {
+        log::debug!("{}: index from cache {}", path.display(), cache_path.display());
+        return Ok((cached.files, cached.entries));
+    }
+    
+    let (files, entries) = parse(path, key)?;
+    let cached = CachedIndex { version: CACHE_VERSION, pak, size: metadata.len(), modified, files, entries };
+    if let Err(e) = write_cache(&cache_path, &cached) {
+        log::warn!("Could not write index cache {}: {}", cache_path.display(), e);
+    }
+    Ok((cached.files, cached.entries))
+}
+
+/// Read a PAK's index: the file list through repak, the entries through our own parser
+fn parse(path: &Path, key: &Aes256) -> Result<PakIndexParts, ExtractError> {
+    let (pak, mut reader) = crate::open_pak_file(path, key, false)?;
+    let pak_index = index::read_index(&mut reader, Some(key))?;
+    Ok((pak.files(), pak_index.entries))
+}
+
+/// The cache file, or `None` if it is missing or unreadable (e.g. from an older version)
+fn read_cache(cache_path: &Path) -> Option<CachedIndex> {
+    let file = File::open(cache_path).ok()?;
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(cached) => Some(cached),
+        Err(e) => {
+            log::debug!("Ignoring index cache {}: {}", cache_path.display(), e);
+            None
+        }
+    }
+}
+
+fn write_cache(cache_path: &Path, cached: &CachedIndex) -> io::Result<()> {
+    if let Some(dir) = cache_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write next to the cache and rename, so a concurrent run never reads half a file
+    let partial = cache_path.with_extension("json.partial");
+    let mut out = BufWriter::new(File::create(&partial)?);
+    serde_json::to_writer(&mut out, cached)?;
+    out.flush()?;
+    fs::rename(&partial, cache_path)
+}
+
+/// `<sha256 of the canonical PAK path>.json`
+fn cache_file_name(pak: &Path) -> String {
+    let hash = Sha256::digest(pak.to_string_lossy().as_bytes());
+    format!("{}.json", hex::encode(hash))
+}
@@ -0,0 +1,58 @@
+//! `info`: header and index facts about a PAK, without extracting anything.
+
+use std::path::Path;
+
+use aes::Aes256;
+use serde::Serialize;
+
+use crate::error::ExtractError;
+use crate::index;
+use crate::say;
+use crate::tree::format_size;
+
+#[derive(Serialize)]
+pub struct PakInfo {
+    pub version: String,
+    pub mount_point: String,
+    pub files: usize,
+    pub encrypted_index: bool,
+    /// Entries whose data is encrypted
+    pub encrypted_files: usize,
+    pub uncompressed_size: u64,
+    /// Bytes the entry data takes up in the PAK
+    pub compressed_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_hash_seed: Option<u64>,
+}
+
+pub fn pak_info(pak_path: &Path, key: &Aes256) -> Result<PakInfo, ExtractError> {
+    let (pak, mut reader) = crate::open_pak(pak_path, key)?;
+    let pak_index = index::read_index(&mut reader, Some(key))?;
+    
+    Ok(PakInfo {
+        version: format!("{:?}", pak.version()),
+        mount_point: pak.mount_point().to_string(),
+        files: pak_index.entries.len(),
+        encrypted_index: pak.encrypted_index(),
+        encrypted_files: pak_index.entries.iter().filter(|e| e.encrypted).count(),
+        uncompressed_size: pak_index.entries.iter().map(|e| e.uncompressed_size).sum(),
+        compressed_size: pak_index.entries.iter().map(|e| e.stored_size()).sum(),
+        path_hash_seed: pak.path_hash_seed(),
+    })
+}
+
+impl PakInfo {
+    pub fn print(&self, pak_path: &Path) {
+        say!("=== {} ===", pak_path.display());
+        say!("  Version:          {}", self.version);
+        say!("  Mount point:      {}", self.mount_point);
+        say!("  Files:            {} ({} encrypted)", self.files, self.encrypted_files);
+        say!("  Encrypted index:  {}", if self.encrypted_index { "yes" } else { "no" });
+        say!("  Uncompressed:     {} ({} bytes)", format_size(self.uncompressed_size), self.uncompressed_size);
+        say!("  Compressed:       {} ({} bytes)", format_size(self.compressed_size), self.compressed_size);
+        match self.path_hash_seed {
+            Some(seed) => say!("  Path hash seed:   {:016x}", seed),
+            None => say!("  Path hash seed:   -"),
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! MotorTown PAK extraction as a library: key loading, opening PAKs and
+//! extracting assets, shared by the `mt-pak-extract` and `verify_pak` binaries.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+use aes::Aes256;
+use aes::cipher::KeyInit;
+use log::LevelFilter;
+use repak::{Compression, PakBuilder, PakReader, Version};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub mod asset_path;
+pub mod backup;
+pub mod batch;
+pub mod bench;
+pub mod container;
+pub mod diff;
+pub mod error;
+pub mod index;
+pub mod index_cache;
+pub mod info;
+pub mod lock;
+pub mod merge;
+#[cfg(feature = "oodle")]
+pub mod oodle;
+pub mod output;
+pub mod package;
+pub mod pak_file;
+pub mod pak_set;
+pub mod progress;
+pub mod repl;
+pub mod search;
+pub mod selftest;
+pub mod show_manifest;
+pub mod signature;
+pub mod split;
+pub mod stats;
+pub mod suggest;
+pub mod tree;
+pub mod user_config;
+pub mod verify_manifest;
+
+use asset_path::normalize_asset_path;
+use container::Container;
+use error::ExtractError;
+use pak_file::PakFile;
+use user_config::UserConfig;
+
+/// Current manifest format, bumped whenever fields change meaning
+pub const MANIFEST_VERSION: u32 = 6;
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    /// Format version; manifests written before versioning count as 1
+    #[serde(default = "first_manifest_version")]
+    pub version: u32,
+    pub extracted: Vec<ExtractedAsset>,
+    /// Assets that could not be extracted (since version 4)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<FailedAsset>,
+    /// Assets whose flat file name was already taken, and how that was resolved (since version 5)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collisions: Vec<CollisionRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification_failed: Vec<VerificationFailure>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExtractedAsset {
+    /// Asset file name without extension, e.g. `Cargos`
+    pub name: String,
+    /// Internal path in the PAK without extension, e.g. `MotorTown/Content/DataAsset/Cargos`
+    pub pak_path: String,
+    /// Extracted files, relative to the output directory. Flat (`Cargos.uasset`)
+    /// unless extracted with --preserve-paths.
+    pub uasset: String,
+    pub uexp: Option<String>,
+    /// Exact internal paths the files were read from, as `pak.files()` lists them
+    /// (since version 6; empty in older manifests)
+    #[serde(default)]
+    pub uasset_entry: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uexp_entry: Option<String>,
+    /// Extracted sizes in bytes
+    #[serde(default)]
+    pub uasset_size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uexp_size: Option<u64>,
+    /// Bytes the asset's entries occupy in the PAK, when the index was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed_size: Option<u64>,
+    /// Compression method of the .uasset entry in the PAK, when the index was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<String>,
+    /// SHA-256 of the extracted files as hex (empty in manifests before version 3)
+    #[serde(default)]
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uexp_sha256: Option<String>,
+    /// Whether the extracted entries matched the hashes stored in the PAK (with --verify)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+fn first_manifest_version() -> u32 {
+    1
+}
+
+/// Read a manifest written by this or an earlier version of the extractor
+pub fn read_manifest(path: &Path) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let manifest: Manifest = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?;
+    check_manifest_version(path, manifest.version)?;
+    Ok(manifest)
+}
+
+/// One row of manifest.csv
+#[derive(Serialize)]
+struct ManifestRow<'a> {
+    name: &'a str,
+    pak_path: &'a str,
+    uasset: &'a str,
+    uexp: Option<&'a str>,
+    uasset_size: u64,
+    sha256: &'a str,
+}
+
+/// Write the extracted assets as CSV, one row per asset. Failures, collisions and
+/// verification results only go into the JSON manifest.
+pub fn write_manifest_csv(manifest: &Manifest, path: &Path) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for asset in &manifest.extracted {
+        writer.serialize(ManifestRow {
+            name: &asset.name,
+            pak_path: &asset.pak_path,
+            uasset: &asset.uasset,
+            uexp: asset.uexp.as_deref(),
+            uasset_size: asset.uasset_size,
+            sha256: &asset.sha256,
+        })?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Refuse manifests from a newer extractor, whose fields may mean something else
+pub fn check_manifest_version(path: &Path, version: u32) -> Result<(), String> {
+    if version > MANIFEST_VERSION {
+        return Err(format!(
+            "{} is a version {} manifest, but this build only reads up to version {}; update mt-pak-extract",
+            path.display(), version, MANIFEST_VERSION
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FailedAsset {
+    /// Internal path in the PAK without extension, as listed in the config
+    pub asset: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollisionRecord {
+    /// Internal path of the asset that collided, without extension
+    pub asset: String,
+    /// Asset that was given the file name first
+    pub conflicts_with: String,
+    pub strategy: batch::CollisionPolicy,
+    /// File written instead, relative to the output directory (`rename` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub written_as: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerificationFailure {
+    pub entry: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Where to get the AES key from, besides the environment and the user config
+#[derive(Default)]
+pub struct KeySource<'a> {
+    /// Key given directly as hex (`--key`)
+    pub key: Option<&'a str>,
+    /// File holding the key (`--keyfile`)
+    pub keyfile: Option<&'a Path>,
+    /// Look in the OS credential store before the environment (`--key-source keyring`)
+    pub keyring: bool,
+}
+
+/// Service and account the AES key is stored under in the OS credential store
+pub const KEYRING_SERVICE: &str = "mt-pak-extract";
+pub const KEYRING_ACCOUNT: &str = "aes-key";
+
+/// Keyfile in the JSON form written by FModel/repak key dumps
+#[derive(Deserialize)]
+struct KeyFile {
+    key: String,
+}
+
+/// Load the AES key, first match wins: `--key`, `--keyfile`, the OS credential
+/// store (only with `source.keyring`), the key environment variable (or `.env`),
+/// then the user config
+pub fn load_key(source: &KeySource, user_config: &UserConfig) -> Result<Aes256, ExtractError> {
+    parse_key(&load_key_hex(source, user_config)?)
+}
+
+/// The AES key as hex, from the same places as `load_key`, without parsing it
+pub fn load_key_hex(source: &KeySource, user_config: &UserConfig) -> Result<String, ExtractError> {
+    if let Some(key_hex) = source.key {
+        log::debug!("Using the AES key from --key");
+        return Ok(key_hex.to_string());
+    }
+    if let Some(path) = source.keyfile {
+        log::debug!("Using the AES key from {}", path.display());
+        return read_keyfile(path);
+    }
+    if source.keyring {
+        match keyring_entry()?.get_password() {
+            Ok(key_hex) => {
+                log::debug!("Using the AES key from the OS credential store");
+                return Ok(key_hex);
+            }
+            Err(keyring::Error::NoEntry) => {
+                log::warn!("No AES key in the OS credential store (run `store-key` to add one); trying the environment and user config");
+            }
+            Err(e) => return Err(ExtractError::Keyring(e)),
+        }
+    }
+    key_hex_from_env(user_config)
+}
+
+/// Save the AES key in the OS credential store, for `--key-source keyring`.
+/// The key is checked first, so a typo is not stored.
+pub fn store_key_in_keyring(key_hex: &str) -> Result<(), ExtractError> {
+    parse_key(key_hex)?;
+    keyring_entry()?.set_password(key_hex.trim()).map_err(ExtractError::Keyring)
+}
+
+fn keyring_entry() -> Result<keyring::Entry, ExtractError> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_ACCOUNT).map_err(ExtractError::Keyring)
+}
+
+/// Read a keyfile holding either the raw hex key or `{"key": "0x..."}`
+pub fn read_keyfile(path: &Path) -> Result<String, ExtractError> {
+    let content = fs::read_to_string(path)?;
+    let content = content.trim();
+    if content.starts_with('{') {
+        let keyfile: KeyFile = serde_json::from_str(content).map_err(|e| ExtractError::InvalidKeyfile {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        Ok(keyfile.key)
+    } else {
+        Ok(content.to_string())
+    }
+}
+
+/// Load the AES key from the environment variable named in the user config
+/// (`KEY` by default, also read from `.env`), falling back to the user config's own key
+pub fn load_key_from_env(user_config: &UserConfig) -> Result<Aes256, ExtractError> {
+    parse_key(&key_hex_from_env(user_config)?)
+}
+
+fn key_hex_from_env(user_config: &UserConfig) -> Result<String, ExtractError> {
+    dotenvy::dotenv().ok();
+    let key_env = user_config.key_env.as_deref().unwrap_or("KEY");
+    let key_hex = match std::env::var(key_env) {
+        Ok(key) => {
+            log::debug!("Using the AES key from {}", key_env);
+            key
+        }
+        Err(_) => {
+            log::debug!("{} is not set, using the AES key from the user config", key_env);
+            user_config.key.clone()
+                .ok_or_else(|| ExtractError::MissingKey { env_var: key_env.to_string() })?
+        }
+    };
+    Ok(key_hex)
+}
+
+/// Parse a 32-byte AES key from hex, with or without a `0x` prefix
+pub fn parse_key(key_hex: &str) -> Result<Aes256, ExtractError> {
+    let key_hex = key_hex.trim();
+    let key_hex = key_hex.strip_prefix("0x").or_else(|| key_hex.strip_prefix("0X")).unwrap_or(key_hex);
+    let key_bytes = hex::decode(key_hex).map_err(ExtractError::InvalidKeyHex)?;
+    Aes256::new_from_slice(&key_bytes)
+        .map_err(|_| ExtractError::KeyWrongLength { got: key_bytes.len() })
+}
+
+/// Open a PAK and parse its index. The reader is positioned for `pak.get`.
+pub fn open_pak(path: impl AsRef<Path>, key: &Aes256) -> Result<(PakReader, BufReader<File>), ExtractError> {
+    let path = path.as_ref();
+    if !path.is_file() {
+        return Err(ExtractError::PakNotFound(path.to_path_buf()));
+    }
+    let mut file = BufReader::new(File::open(path)?);
+    let pak = read_pak(path, &mut file, key)?;
+    Ok((pak, file))
+}
+
+/// Like `open_pak`, but reads through a memory map of the whole PAK with `mmap`
+pub fn open_pak_file(path: &Path, key: &Aes256, mmap: bool) -> Result<(PakReader, PakFile), ExtractError> {
+    if !path.is_file() {
+        return Err(ExtractError::PakNotFound(path.to_path_buf()));
+    }
+    let mut file = PakFile::open(path, mmap)?;
+    let pak = read_pak(path, &mut file, key)?;
+    Ok((pak, file))
+}
+
+fn read_pak<R: Read + Seek>(path: &Path, reader: &mut R, key: &Aes256) -> Result<PakReader, ExtractError> {
+    if is_iostore(path) {
+        return Err(ExtractError::IoStoreUnsupported(path.to_path_buf()));
+    }
+    let builder = PakBuilder::new().key(key.clone());
+    #[cfg(feature = "oodle")]
+    let builder = builder.oodle(oodle::decompressor);
+    let result = builder.reader(reader);
+    // A wrong key makes repak fail with a parse error (or read garbage), so
+    // check the index ourselves to name the real problem
+    if !index::key_decrypts_index(reader, key).unwrap_or(true) {
+        return Err(ExtractError::WrongKey(path.to_path_buf()));
+    }
+    let pak = result.map_err(ExtractError::PakOpen)?;
+    log::debug!("{}: {:?}, mount point {}, {} entries", path.display(), pak.version(), pak.mount_point(), pak.files().len());
+    Ok(pak)
+}
+
+/// Whether `path` is an IoStore container (`.utoc` table of contents or `.ucas` data),
+/// going by its extension
+pub fn is_iostore(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("utoc") || ext.eq_ignore_ascii_case("ucas"))
+}
+
+/// Send diagnostics to stderr at `level` without timestamps. `RUST_LOG` overrides the level.
+pub fn init_logging(level: LevelFilter) {
+    let logger = env_logger::Builder::new()
+        .filter_level(level)
+        .format_timestamp(None)
+        .format_target(false)
+        .parse_default_env()
+        .build();
+    log::set_max_level(logger.filter());
+    let _ = log::set_boxed_logger(Box::new(output::ReportingLogger(logger)));
+}
+
+/// Parse a PAK version name such as `V11` or `v8b`
+pub fn parse_version(s: &str) -> Result<Version, String> {
+    match s.to_uppercase().as_str() {
+        "V1" => Ok(Version::V1),
+        "V2" => Ok(Version::V2),
+        "V3" => Ok(Version::V3),
+        "V4" => Ok(Version::V4),
+        "V5" => Ok(Version::V5),
+        "V6" => Ok(Version::V6),
+        "V7" => Ok(Version::V7),
+        "V8A" => Ok(Version::V8A),
+        "V8B" => Ok(Version::V8B),
+        "V9" => Ok(Version::V9),
+        "V10" => Ok(Version::V10),
+        "V11" => Ok(Version::V11),
+        _ => Err(format!("Unknown PAK version '{}' (expected V1-V7, V8A, V8B, V9, V10 or V11)", s)),
+    }
+}
+
+/// Parse a compression method name as stored in PAKs (`Zlib`, `Oodle`, ...)
+pub fn parse_compression(name: &str) -> Result<Compression, String> {
+    match name.to_lowercase().as_str() {
+        "zlib" => Ok(Compression::Zlib),
+        "gzip" => Ok(Compression::Gzip),
+        "oodle" => Ok(Compression::Oodle),
+        "zstd" => Ok(Compression::Zstd),
+        "lz4" => Ok(Compression::LZ4),
+        _ => Err(format!("Unsupported compression method for repacking: {}", name)),
+    }
+}
+
+/// SHA-256 of extracted data as lowercase hex, as recorded in the manifest
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Extract any entry to `out_dir`, keeping its internal directory structure.
+/// Returns the path it was written to.
+pub fn extract_entry<R: Read + Seek>(
+    pak: &dyn Container,
+    reader: &mut R,
+    entry_path: &str,
+    out_dir: &Path,
+) -> Result<PathBuf, ExtractError> {
+    check_entry_path(entry_path)?;
+    
+    let data = pak.get(entry_path, reader)?;
+    let out_path = out_dir.join(entry_path);
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    backup::before_write(&out_path, &data)?;
+    fs::write(&out_path, &data)?;
+    Ok(out_path)
+}
+
+/// Like `extract_entry`, but without holding the whole entry in memory. Plain
+/// (unencrypted, uncompressed) entries are copied from the PAK to the file in
+/// chunks; repak decodes the others straight into the buffered file.
+pub fn stream_entry<R: Read + Seek>(
+    pak: &dyn Container,
+    reader: &mut R,
+    entry: &index::EntryInfo,
+    out_dir: &Path,
+) -> Result<PathBuf, ExtractError> {
+    check_entry_path(&entry.path)?;
+    
+    let out_path = out_dir.join(&entry.path);
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let backup = backup::before_stream(&out_path)?;
+    let mut out = BufWriter::new(File::create(&out_path)?);
+    if entry.compression.is_none() && !entry.encrypted {
+        reader.seek(SeekFrom::Start(entry.data_offset))?;
+        let copied = io::copy(&mut reader.by_ref().take(entry.uncompressed_size), &mut out)?;
+        if copied != entry.uncompressed_size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{}: PAK ends after {} of {} bytes", entry.path, copied, entry.uncompressed_size),
+            ).into());
+        }
+    } else {
+        pak.read_file(&entry.path, reader, &mut out)
+            .map_err(|e| e.with_entry(Some(entry)))?;
+    }
+    out.flush()?;
+    backup::after_write(&out_path, backup)?;
+    Ok(out_path)
+}
+
+/// `err` with the failed entry's storage looked up in the PAK's index, for callers
+/// that don't have the index at hand. Other errors are returned as they are.
+pub fn describe_read_error<R: Read + Seek>(err: ExtractError, reader: &mut R, key: &Aes256) -> ExtractError {
+    let ExtractError::PakRead { path, storage: None, .. } = &err else {
+        return err;
+    };
+    let pak_index = index::read_index(reader, Some(key)).ok();
+    let entry = pak_index.as_ref().and_then(|pak_index| pak_index.find(path));
+    err.with_entry(entry)
+}
+
+/// Extract an asset's `.uasset` and (if present, unless `exclude_uexp`) `.uexp` into
+/// `out_dir`, named after the asset's file name, or under its internal path with `preserve_paths`.
+pub fn extract_asset<R: Read + Seek>(
+    pak: &dyn Container,
+    reader: &mut R,
+    asset_path: &str,
+    out_dir: &Path,
+    preserve_paths: bool,
+    exclude_uexp: bool,
+) -> Result<ExtractedAsset, ExtractError> {
+    let asset = normalize_asset_path(asset_path);
+    let asset_path = asset.as_str();
+    let name = asset.file_name();
+    let out_name = if preserve_paths {
+        check_entry_path(asset_path)?;
+        asset_path
+    } else {
+        name
+    };
+    
+    let uasset_path = asset.uasset();
+    let uasset_data = match pak.get(&uasset_path, reader) {
+        Ok(data) => data,
+        Err(_) if !pak.files().contains(&uasset_path) => {
+            return Err(ExtractError::AssetNotFound(uasset_path));
+        }
+        Err(e) => return Err(e),
+    };
+    let uasset = format!("{}.uasset", out_name);
+    let uasset_out = out_dir.join(&uasset);
+    if let Some(parent) = uasset_out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    backup::before_write(&uasset_out, &uasset_data)?;
+    fs::write(uasset_out, &uasset_data)?;
+    
+    let uexp_path = asset.uexp();
+    let uexp_result = if exclude_uexp { None } else { pak.get(&uexp_path, reader).ok() };
+    let (uexp, uexp_data) = match uexp_result {
+        Some(uexp_data) => {
+            let uexp = format!("{}.uexp", out_name);
+            let uexp_out = out_dir.join(&uexp);
+            backup::before_write(&uexp_out, &uexp_data)?;
+            fs::write(uexp_out, &uexp_data)?;
+            (Some(uexp), Some(uexp_data))
+        }
+        None => (None, None),
+    };
+    
+    Ok(ExtractedAsset {
+        name: name.to_string(),
+        pak_path: asset_path.to_string(),
+        uasset,
+        uexp_entry: uexp.as_ref().map(|_| uexp_path),
+        uexp,
+        uasset_entry: uasset_path,
+        uasset_size: uasset_data.len() as u64,
+        uexp_size: uexp_data.as_ref().map(|data| data.len() as u64),
+        compressed_size: None,
+        compression: None,
+        sha256: sha256_hex(&uasset_data),
+        uexp_sha256: uexp_data.as_deref().map(sha256_hex),
+        verified: None,
+    })
+}
+
+/// Refuse internal paths that would be written outside the output directory
+pub fn check_entry_path(path: &str) -> Result<(), ExtractError> {
+    if Path::new(path).components().all(|c| matches!(c, Component::Normal(_))) {
+        Ok(())
+    } else {
+        Err(ExtractError::UnsafeEntryPath(path.to_string()))
+    }
+}
+
+/// Folder `--out-layout mount` writes entries under: the mount point relative to the
+/// game root, e.g. `../../../MotorTown/` -> `MotorTown/` (empty for `../../../`)
+pub fn mount_layout_dir(mount_point: &str) -> Result<String, ExtractError> {
+    let dir = package::mount_relative(mount_point);
+    if !dir.is_empty() {
+        check_entry_path(&dir)?;
+    }
+    Ok(dir)
+}
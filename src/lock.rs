@@ -0,0 +1,93 @@
+//! Lockfile guarding an output directory against concurrent extraction runs.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+const LOCK_FILE: &str = ".mt-pak-extract.lock";
+
+/// Lockfiles this process holds, for the Ctrl-C handler to remove
+static HELD: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+/// ctrlc allows one handler per process, so it is installed on the first acquire
+static HANDLER: OnceLock<Result<(), String>> = OnceLock::new();
+
+/// Held for the duration of a run; the lockfile is removed on drop and on Ctrl-C.
+pub struct OutputLock {
+    path: PathBuf,
+}
+
+impl OutputLock {
+    /// Create the lockfile in `dir`, failing if another run holds it.
+    /// With `force`, an existing (stale) lock is broken first.
+    pub fn acquire(dir: &Path, force: bool) -> Result<OutputLock, Box<dyn std::error::Error>> {
+        let path = dir.join(LOCK_FILE);
+        
+        if force && path.exists() {
+            log::warn!("Breaking existing lock: {}", path.display());
+            fs::remove_file(&path)?;
+        }
+        
+        HANDLER.get_or_init(|| {
+            ctrlc::set_handler(|| {
+                for path in HELD.lock().unwrap_or_else(PoisonError::into_inner).iter() {
+                    let _ = fs::remove_file(path);
+                }
+                log::warn!("Interrupted, released lock");
+                std::process::exit(130);
+            }).map_err(|e| format!("Could not install the Ctrl-C handler: {}", e))
+        }).clone()?;
+        
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(&path).unwrap_or_default();
+                return Err(format!(
+                    "{} is locked by another run (pid {}). Use --force to break a stale lock.",
+                    dir.display(),
+                    holder.trim()
+                ).into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        // From here on the lockfile exists, so it is owned by a lock that removes it on drop
+        HELD.lock().unwrap_or_else(PoisonError::into_inner).push(path.clone());
+        let lock = OutputLock { path };
+        writeln!(file, "{}", std::process::id())?;
+        Ok(lock)
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        HELD.lock().unwrap_or_else(PoisonError::into_inner).retain(|path| *path != self.path);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn locks_can_be_taken_repeatedly_in_one_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock_path = dir.path().join(LOCK_FILE);
+        
+        let lock = OutputLock::acquire(dir.path(), false).unwrap();
+        assert!(lock_path.exists());
+        assert!(OutputLock::acquire(dir.path(), false).is_err());
+        drop(lock);
+        assert!(!lock_path.exists());
+        
+        let other = tempfile::tempdir().unwrap();
+        let first = OutputLock::acquire(dir.path(), false).unwrap();
+        let second = OutputLock::acquire(other.path(), false).unwrap();
+        let held = |path: &PathBuf| HELD.lock().unwrap().contains(path);
+        assert!(held(&first.path) && held(&second.path));
+        let paths = [first.path.clone(), second.path.clone()];
+        drop((first, second));
+        assert!(!paths.iter().any(held));
+        assert!(!lock_path.exists());
+    }
+}
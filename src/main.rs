@@ -1,215 +1,1276 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::BufReader;
-use std::path::Path;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
-use aes::Aes256;
-use aes::cipher::KeyInit;
-use repak::PakBuilder;
+use clap::Parser;
+use log::{debug, error, info, trace, warn};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use mt_pak_extract::error::{self, ExtractError};
+use mt_pak_extract::asset_path::{normalize_asset_path, normalize_slashes, AssetPath};
+use mt_pak_extract::index::EntryInfo;
+use mt_pak_extract::pak_file::PakFile;
+use mt_pak_extract::{
+    backup, batch, bench, diff, index, index_cache, info, lock, merge, output, package, pak_set, progress, repl, say, search, selftest, show_manifest, split, stats, suggest, tree, user_config, verify_manifest,
+    CollisionRecord, ExtractedAsset, FailedAsset, MANIFEST_VERSION, Manifest, VerificationFailure,
+};
+
+use cli::{Cli, Command, Format, KeyOrigin, ListSort, ManifestFormat, OutLayout, OutputFormat};
+
+mod app_config;
+mod cli;
+
+/// Assets to extract: every explicit `assets` entry, plus the assets matching
+/// an `include` glob and no `exclude` glob
 #[derive(Deserialize)]
 struct Config {
+    #[serde(default)]
     assets: Vec<String>,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// One line of `--format ndjson` output for list/search
+#[derive(Serialize)]
+struct ListEntry<'a> {
+    path: &'a str,
 }
 
+/// One element of `list --json`
 #[derive(Serialize)]
-struct Manifest {
-    extracted: Vec<ExtractedAsset>,
+struct ListedEntry<'a> {
+    path: &'a str,
+    size: u64,
+    compressed_size: u64,
+    compression: &'a str,
+    encrypted: bool,
 }
 
+/// One row of the offsets-csv inventory
 #[derive(Serialize)]
-struct ExtractedAsset {
-    name: String,
-    pak_path: String,
-    uasset: String,
-    uexp: Option<String>,
+struct InventoryRow<'a> {
+    path: &'a str,
+    header_offset: u64,
+    data_offset: u64,
+    stored_size: u64,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    compression: &'a str,
+    encrypted: bool,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = std::env::args().collect();
+/// One line of `probe` output
+#[derive(Serialize)]
+struct ProbeRecord<'a> {
+    path: &'a str,
+    uncompressed_size: u64,
+    /// Hex of the first bytes of the entry
+    bytes: String,
+    /// Whether `bytes` is shorter than the entry
+    truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            // Usage errors exit 1 like other unexpected errors (2 means asset not found)
+            let _ = e.print();
+            return if e.use_stderr() { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+        }
+    };
     
-    let list_mode = args.contains(&"--list".to_string());
-    let config_idx = args.iter().position(|a| a == "--config");
+    mt_pak_extract::init_logging(cli.log_level());
+    let json = cli.output_format == OutputFormat::Json;
+    output::set_json(json);
     
-    println!("=== MotorTown PAK Asset Extractor ===");
-    println!("Usage: {} [--list] [--config <file>] [asset_path]", args[0]);
-    println!("  --list: Show all DataAsset files in PAK");
-    println!("  --config <file>: Batch extract assets listed in JSON config");
-    println!("  asset_path: Extract single asset (default: Cargos)");
-    println!();
+    let started = Instant::now();
+    let exit_code = match run(cli) {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("{}", e);
+            error::exit_code(e.as_ref())
+        }
+    };
+    if json && let Err(e) = output::print_result(exit_code, started.elapsed()) {
+        error!("Could not write the result object: {}", e);
+    }
+    ExitCode::from(exit_code)
+}
+
+fn run(mut cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let mut timings = Timings::default();
+    app_config::load()?.merge_into(&mut cli);
+    let encryption_filter = cli.encryption_filter();
+    let ndjson = cli.format == Format::Ndjson;
+    let pak_arg = cli.pak;
+    let cli_quiet = cli.quiet;
+    let ignore_errors = cli.ignore_errors;
+    let mmap = cli.mmap;
+    backup::set_enabled(cli.backup);
+    let no_cache = cli.no_cache;
+    #[cfg(feature = "oodle")]
+    if let Some(library) = &cli.oodle {
+        mt_pak_extract::oodle::set_library(library);
+    }
     
-    // Load AES key from .env file
-    dotenvy::dotenv().ok();
-    let key_hex = std::env::var("KEY")?;
+    let command = match cli.command {
+        Some(_) if cli.interactive => {
+            return Err("--interactive opens a prompt instead of running a subcommand; leave the subcommand out".into());
+        }
+        Some(command) => command,
+        None if cli.interactive => Command::Interactive,
+        None => {
+            warn!("Running without a subcommand is deprecated; use `extract MotorTown/Content/DataAsset/Cargos` (see --help)");
+            Command::Extract {
+                asset: Some("MotorTown/Content/DataAsset/Cargos".to_string()),
+                by_package: None,
+                assume_mount: None,
+                by_hash: None,
+                all: false,
+                prefix: None,
+                stdin: false,
+                ext: Vec::new(),
+                stream: false,
+                skip: 0,
+                limit: None,
+                jobs: None,
+                preserve_paths: false,
+                exclude_uexp: false,
+                out_layout: OutLayout::Internal,
+                force: false,
+                out: None,
+            }
+        }
+    };
     
-    let key_hex = key_hex.strip_prefix("0x").unwrap_or(&key_hex);
-    let key_bytes: [u8; 32] = hex::decode(key_hex)?
-        .try_into()
-        .map_err(|_| "Key must be 32 bytes")?;
+    // extract --stdin is a batch whose asset list comes from stdin instead of a config
+    let command = match command {
+        Command::Extract { stdin: true, skip, limit, ref ext, .. } if skip > 0 || limit.is_some() || !ext.is_empty() => {
+            return Err("--skip, --limit and --ext only apply to --all and --prefix".into());
+        }
+        Command::Extract { stdin: true, preserve_paths, exclude_uexp, jobs, out_layout, force, out, .. } => Command::Batch {
+            config: Vec::new(),
+            verify: false,
+            append_manifest: false,
+            sort_manifest: false,
+            manifest_format: ManifestFormat::Json,
+            write_queue: 16,
+            force,
+            preserve_paths,
+            exclude_uexp,
+            out_layout,
+            jobs,
+            dry_run: false,
+            on_collision: batch::CollisionPolicy::Rename,
+            out,
+        },
+        command => command,
+    };
     
-    let aes_key = Aes256::new_from_slice(&key_bytes)?;
+    // Handle show-manifest command (no PAK or key needed)
+    if let Command::ShowManifest { manifest, json } = &command {
+        return show_manifest::show_manifest(manifest, *json);
+    }
+    if let Command::VerifyManifest { manifest } = &command {
+        return verify_manifest::verify_manifest(manifest);
+    }
     
-    // Open the PAK file
-    let pak_path = "MotorTown-WindowsServer.pak";
-    let mut file = BufReader::new(File::open(pak_path)?);
+    // Keep stdout clean for NDJSON, JSON and cat consumers
+    let quiet = ndjson || matches!(command, Command::List { count_only: true, .. } | Command::Search { count_only: true, .. } | Command::Cat { .. } | Command::Diff { json: true, .. } | Command::Info { json: true, .. } | Command::List { json: true, .. } | Command::Tree { json: true, .. });
+    if !quiet && !cli_quiet {
+        say!("=== MotorTown PAK Asset Extractor ===");
+    }
     
-    println!("Opening PAK file: {}", pak_path);
+    let user_config = user_config::load()?;
     
-    let pak = PakBuilder::new()
-        .key(aes_key)
-        .reader(&mut file)?;
+    // Load AES key: --key, --keyfile, the OS credential store (--key-source keyring), then .env, then the user config
+    let key_source = mt_pak_extract::KeySource {
+        key: cli.key.as_deref(),
+        keyfile: cli.keyfile.as_deref(),
+        keyring: cli.key_source == Some(KeyOrigin::Keyring),
+    };
     
-    // Handle --list mode
-    if list_mode {
-        println!("=== Available DataAsset files ===");
-        let mut count = 0;
-        for path in pak.files() {
-            if path.ends_with(".uasset") && path.contains("DataAsset") {
-                println!("  {}", path.trim_end_matches(".uasset"));
-                count += 1;
-            }
+    // Handle store-key command (copies the key from wherever it is now)
+    if let Command::StoreKey = &command {
+        let key_hex = mt_pak_extract::load_key_hex(&key_source, &user_config)?;
+        mt_pak_extract::store_key_in_keyring(&key_hex)?;
+        say!("Stored the AES key in the OS credential store (service {}, account {})", mt_pak_extract::KEYRING_SERVICE, mt_pak_extract::KEYRING_ACCOUNT);
+        say!("Use it with --key-source keyring; .env and --key are no longer needed");
+        return Ok(());
+    }
+    let aes_key = mt_pak_extract::load_key(&key_source, &user_config)?;
+    
+    // Handle split command (works on any PAK, not the default one)
+    if let Command::Split { pak, max_size } = &command {
+        return split::split_pak(pak, *max_size, &aes_key);
+    }
+    
+    // Handle merge command (reads the given PAKs, not the default one)
+    if let Command::Merge { output, paks } = &command {
+        return merge::merge_paks(paks, output, &aes_key);
+    }
+    
+    // Handle info command (describes the given PAK)
+    if let Command::Info { pak, json } = &command {
+        let pak_info = info::pak_info(pak, &aes_key)?;
+        if *json {
+            say!("{}", serde_json::to_string_pretty(&pak_info)?);
+        } else {
+            pak_info.print(pak);
         }
-        println!("Total: {} DataAsset files", count);
         return Ok(());
     }
     
-    // Handle --search mode
-    let search_idx = args.iter().position(|a| a == "--search");
-    if let Some(idx) = search_idx {
-        let pattern = args.get(idx + 1)
-            .ok_or("--search requires a pattern")?;
+    // Handle diff command (compares two given PAKs)
+    if let Command::Diff { pak_a, pak_b, json } = &command {
+        let pak_diff = diff::diff_paks(pak_a, pak_b, &aes_key)?;
+        if *json {
+            say!("{}", serde_json::to_string_pretty(&pak_diff)?);
+        } else {
+            pak_diff.print(pak_a, pak_b);
+        }
+        return Ok(());
+    }
+    
+    // Handle selftest command (round-trips the given PAK through scratch space)
+    if let Command::Selftest { pak, sample } = &command {
+        return selftest::selftest(pak, &aes_key, usize::from(*sample));
+    }
+    
+    // Open the PAK files
+    let pak_paths = if pak_arg.is_empty() {
+        vec![user_config.pak.as_deref().map_or_else(|| PathBuf::from("MotorTown-WindowsServer.pak"), PathBuf::from)]
+    } else {
+        pak_arg
+    };
+    if pak_paths.len() > 1
+        && !matches!(command, Command::List { tree: false, .. } | Command::Tree { .. } | Command::Stats | Command::Search { .. } | Command::Cat { .. } | Command::Extract { by_hash: None, stdin: false, .. } | Command::Interactive)
+    {
+        return Err("Only list, search, cat, extract and --interactive can read several PAKs; pass --pak once".into());
+    }
+    for pak_path in &pak_paths {
+        if !pak_path.is_file() {
+            return Err(ExtractError::PakNotFound(pak_path.clone()).into());
+        }
+        if mt_pak_extract::is_iostore(pak_path) {
+            return Err(ExtractError::IoStoreUnsupported(pak_path.clone()).into());
+        }
+        info!("Opening PAK file: {}", pak_path.display());
+    }
+    let pak_path = pak_paths[0].as_path();
+    
+    // Handle --by-hash mode before repak reads the index, which it can't
+    // do for PAKs that only carry a path hash index
+    if let Command::Extract { by_hash: Some(hash_arg), force, out, .. } = &command {
+        let hash = u64::from_str_radix(hash_arg.trim_start_matches("0x"), 16)
+            .map_err(|e| format!("Invalid path hash '{}': {}", hash_arg, e))?;
         
-        println!("=== Searching for assets containing '{}' ===", pattern);
-        let mut count = 0;
-        for path in pak.files() {
-            if path.ends_with(".uasset") && path.to_lowercase().contains(&pattern.to_lowercase()) {
-                println!("  {}", path.trim_end_matches(".uasset"));
-                count += 1;
-            }
+        let phase = Instant::now();
+        let mut file = BufReader::new(File::open(pak_path)?);
+        if !index::key_decrypts_index(&mut file, &aes_key)? {
+            return Err(ExtractError::WrongKey(pak_path.to_path_buf()).into());
         }
-        println!("Total: {} matching assets", count);
+        timings.record("open", phase);
+        let phase = Instant::now();
+        let entries = index::read_hashed_entries(&mut file, Some(&aes_key))?;
+        timings.record("index", phase);
+        let (_, entry) = entries.iter()
+            .find(|(h, _)| *h == hash)
+            .ok_or_else(|| ExtractError::AssetNotFound(format!("path hash {:016x}", hash)))?;
+        
+        info!("Extracting entry with path hash {:016x}", hash);
+        let phase = Instant::now();
+        let data = index::read_entry_data(&mut file, entry, Some(&aes_key))?;
+        let out_dir = resolve_out_dir(out.clone(), &user_config)?;
+        fs::create_dir_all(&out_dir)?;
+        let _lock = lock::OutputLock::acquire(&out_dir, *force)?;
+        let output_path = out_dir.join(format!("{:016x}.bin", hash));
+        backup::before_write(&output_path, &data)?;
+        fs::write(&output_path, &data)?;
+        say!("Saved: {} ({} bytes)", output_path.display(), data.len());
+        timings.record("extract", phase);
+        
+        let mut written = Written::since(started);
+        written.add(&output_path);
+        written.log();
+        timings.log();
+        return Ok(());
+    }
+    
+    // list, search, stats and tree only need the index, which comes from the
+    // index cache while the PAKs are unchanged
+    if matches!(command, Command::List { .. } | Command::Search { .. } | Command::Stats | Command::Tree { .. }) {
+        let phase = Instant::now();
+        let listing = index_cache::load(&pak_paths, &aes_key, !no_cache)?;
+        timings.record("index", phase);
+        list_index(command, &listing, ndjson, encryption_filter)?;
+        timings.log();
         return Ok(());
     }
     
-    // Handle --config mode (batch extraction)
-    if let Some(idx) = config_idx {
-        let config_path = args.get(idx + 1)
-            .ok_or("--config requires a file path")?;
+    let phase = Instant::now();
+    let mut paks = pak_set::PakSet::open(&pak_paths, &aes_key, mmap)?;
+    timings.record("open", phase);
+    let (pak, file) = paks.base();
+    
+    match command {
+        // Hidden: time the phases of extracting one entry
+        Command::BenchEntry { path, iterations } => {
+            bench::bench_entry(file, pak, &aes_key, &path, iterations)?;
+        }
         
-        println!("Loading config: {}", config_path);
-        let config_content = fs::read_to_string(config_path)?;
-        let config: Config = serde_json::from_str(&config_content)?;
+        Command::Interactive => {
+            if output::is_json() {
+                return Err("--interactive can't be combined with --output-format json".into());
+            }
+            let entries = paks.entries(&aes_key)?;
+            let out_dir = resolve_out_dir(None, &user_config)?;
+            repl::run(&mut paks, &entries, &out_dir)?;
+        }
         
-        // Create output directory
-        let out_dir = Path::new("out");
-        fs::create_dir_all(out_dir)?;
+        // Only the entry's bytes go to stdout; diagnostics go to stderr through the logger
+        Command::Cat { entry: entry_path } => {
+            if output::is_json() {
+                return Err("cat writes the entry to stdout and can't be combined with --output-format json".into());
+            }
+            let entry_path = normalize_slashes(&entry_path);
+            let entry_path = if paks.resolve(&entry_path).is_some() {
+                entry_path
+            } else {
+                normalize_asset_path(&entry_path).uasset()
+            };
+            let (pak, file, source) = paks.resolve(&entry_path)
+                .ok_or_else(|| ExtractError::AssetNotFound(entry_path.clone()))?;
+            debug!("Reading {} from {}", entry_path, source.display());
+            
+            let mut out = BufWriter::new(io::stdout().lock());
+            pak.read_file(&entry_path, file, &mut out)
+                .map_err(|e| mt_pak_extract::describe_read_error(e, file, &aes_key))?;
+            out.flush()?;
+        }
         
-        println!("Extracting {} assets to {}/", config.assets.len(), out_dir.display());
+        // Entry location for hex editors
+        Command::Offsets { entry: entry_path } => {
+            let entry_path = normalize_slashes(&entry_path);
+            let pak_index = index::read_index(file, Some(&aes_key))?;
+            let uasset_path = format!("{}.uasset", entry_path);
+            let entry = pak_index.entries.iter()
+                .find(|e| e.path == entry_path)
+                .or_else(|| pak_index.entries.iter().find(|e| e.path == uasset_path))
+                .ok_or_else(|| ExtractError::AssetNotFound(entry_path.clone()))?;
+            
+            say!("=== {} ===", entry.path);
+            say!("  Header offset: 0x{:X} ({})", entry.offset, entry.offset);
+            say!("  Data offset:   0x{:X} ({})", entry.data_offset, entry.data_offset);
+            say!("  Data length:   {} bytes on disk, {} bytes uncompressed", entry.stored_size(), entry.uncompressed_size);
+            say!("  Compression:   {}", entry.compression_name());
+            say!("  Encrypted:     {}", if entry.encrypted { "yes" } else { "no" });
+            if entry.compression.is_some() {
+                say!("  Blocks:");
+                for (i, (start, end)) in entry.blocks.iter().enumerate() {
+                    say!("    [{}] 0x{:X}..0x{:X} ({} bytes)", i, start, end, end - start);
+                }
+            }
+        }
         
-        let mut manifest = Manifest { extracted: Vec::new() };
+        // Offsets of every entry
+        Command::OffsetsCsv { file: csv_path } => {
+            let pak_index = index::read_index(file, Some(&aes_key))?;
+            
+            let rows = pak_index.entries.iter().map(|entry| InventoryRow {
+                path: &entry.path,
+                header_offset: entry.offset,
+                data_offset: entry.data_offset,
+                stored_size: entry.stored_size(),
+                compressed_size: entry.compressed_size,
+                uncompressed_size: entry.uncompressed_size,
+                compression: entry.compression_name(),
+                encrypted: entry.encrypted,
+            });
+            if ndjson {
+                let mut out = BufWriter::new(File::create(&csv_path)?);
+                for row in rows {
+                    serde_json::to_writer(&mut out, &row)?;
+                    writeln!(out)?;
+                }
+                out.flush()?;
+            } else {
+                let mut writer = csv::Writer::from_path(&csv_path)?;
+                for row in rows {
+                    writer.serialize(row)?;
+                }
+                writer.flush()?;
+            }
+            
+            say!("Wrote offsets of {} entries to {}", pak_index.entries.len(), csv_path.display());
+        }
+        
+        // First bytes of every entry, for signature scans
+        Command::Probe { bytes: probe_len, out: probe_path } => {
+            let pak_index = index::read_index(file, Some(&aes_key))?;
+            
+            let mut out = BufWriter::new(File::create(&probe_path)?);
+            let mut probed = 0;
+            let mut errors = 0;
+            for entry in &pak_index.entries {
+                if encryption_filter.is_some_and(|want| entry.encrypted != want) {
+                    continue;
+                }
+                
+                // Our own reader only decompresses the blocks it needs, but doesn't
+                // know Oodle; fall back to a full extraction through repak for that
+                let data = index::read_entry_prefix(file, entry, Some(&aes_key), probe_len)
+                    .or_else(|_| pak.get(&entry.path, file).map(|mut data| {
+                        data.truncate(probe_len);
+                        data
+                    }));
+                let record = match data {
+                    Ok(data) => ProbeRecord {
+                        path: &entry.path,
+                        uncompressed_size: entry.uncompressed_size,
+                        bytes: hex::encode(&data),
+                        truncated: (data.len() as u64) < entry.uncompressed_size,
+                        error: None,
+                    },
+                    Err(e) => {
+                        errors += 1;
+                        ProbeRecord {
+                            path: &entry.path,
+                            uncompressed_size: entry.uncompressed_size,
+                            bytes: String::new(),
+                            truncated: true,
+                            error: Some(e.to_string()),
+                        }
+                    }
+                };
+                serde_json::to_writer(&mut out, &record)?;
+                writeln!(out)?;
+                probed += 1;
+            }
+            out.flush()?;
+            
+            say!("Probed {} entries ({} failed) into {}", probed, errors, probe_path.display());
+        }
         
-        for asset_path in &config.assets {
-            let asset_path = asset_path
-                .trim_end_matches(".uasset")
-                .trim_end_matches(".uexp");
+        Command::Batch { config: config_paths, verify, append_manifest, sort_manifest, manifest_format, write_queue, force, preserve_paths, exclude_uexp, out_layout, jobs, dry_run, out, on_collision } => {
+            if append_manifest && manifest_format == ManifestFormat::Csv {
+                return Err("--append-manifest needs the JSON manifest".into());
+            }
+            let preserve_paths = preserve_paths || out_layout == OutLayout::Mount;
+            let mount_dir = match out_layout {
+                OutLayout::Mount => mount_layout_dir([pak.mount_point()].into_iter())?,
+                OutLayout::Internal => String::new(),
+            };
+            let mut assets = if config_paths.is_empty() {
+                info!("Reading asset paths from stdin");
+                read_asset_list(io::stdin().lock())?
+            } else {
+                let files = pak.files();
+                let mut per_config = Vec::new();
+                for config_path in &config_paths {
+                    info!("Loading config: {}", config_path.display());
+                    let config = load_config(config_path)?;
+                    per_config.push((config_path.as_path(), resolve_assets(&config, &files)?));
+                }
+                merge_config_assets(per_config)
+            };
+            if assets.is_empty() {
+                match config_paths.as_slice() {
+                    [] => warn!("No asset paths on stdin"),
+                    [config_path] => warn!("{} selects no assets", config_path.display()),
+                    _ => warn!("None of the {} configs selects any assets", config_paths.len()),
+                }
+            }
             
-            let name = Path::new(asset_path)
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("asset");
+            // Written flat, assets from different folders can share a file name
+            let (flat_names, collisions) = if preserve_paths {
+                (HashMap::new(), Vec::new())
+            } else {
+                let batch::FlatNames { renamed, skipped, collisions } = batch::resolve_flat_names(&assets, on_collision)?;
+                for collision in &collisions {
+                    match &collision.written_as {
+                        Some(written_as) => warn!("{} has the same file name as {}; writing it as {}", collision.asset, collision.conflicts_with, written_as),
+                        None => warn!("{} has the same file name as {}; skipping it", collision.asset, collision.conflicts_with),
+                    }
+                }
+                assets.retain(|asset| !skipped.iter().any(|s| s == normalize_asset_path(asset).as_str()));
+                (renamed, collisions)
+            };
             
-            let uasset_pak_path = format!("{}.uasset", asset_path);
-            let uexp_pak_path = format!("{}.uexp", asset_path);
+            let out_dir = &resolve_out_dir(out, &user_config)?;
+            // The manifest stays at the top of the output directory, the files go under the mount folder
+            let files_dir = &out_dir.join(&mount_dir);
+            if dry_run {
+                say!("Dry run: planning {} assets into {}/", assets.len(), files_dir.display());
+                let pak_index = index::read_index(file, Some(&aes_key))?;
+                let planned = batch::print_plan(&pak_index, &assets, files_dir, preserve_paths, exclude_uexp, Some(&flat_names), encryption_filter);
+                say!("\n=== Dry run: {} of {} assets would be extracted, nothing written ===", planned, assets.len());
+                return Ok(());
+            }
             
-            print!("  {} ... ", name);
+            // Create output directory
+            fs::create_dir_all(out_dir)?;
+            let _lock = lock::OutputLock::acquire(out_dir, force)?;
             
-            match pak.get(&uasset_pak_path, &mut file) {
-                Ok(uasset_data) => {
-                    let uasset_out = out_dir.join(format!("{}.uasset", name));
-                    fs::write(&uasset_out, &uasset_data)?;
-                    
-                    let uexp_out = match pak.get(&uexp_pak_path, &mut file) {
-                        Ok(uexp_data) => {
-                            let path = out_dir.join(format!("{}.uexp", name));
-                            fs::write(&path, &uexp_data)?;
-                            Some(format!("{}.uexp", name))
+            info!("Extracting {} assets to {}/", assets.len(), out_dir.display());
+            
+            let mut manifest = Manifest {
+                version: MANIFEST_VERSION,
+                extracted: Vec::new(),
+                failed: Vec::new(),
+                collisions,
+                verification_failed: Vec::new(),
+            };
+            
+            let phase = Instant::now();
+            let pak_index = index::read_index(file, Some(&aes_key))?;
+            timings.record("index", phase);
+            
+            let progress = progress::Progress::new(assets.len() as u64);
+            let options = batch::BatchOptions {
+                pak_path,
+                out_dir: files_dir,
+                encryption_filter,
+                verify,
+                index: Some(&pak_index),
+                write_queue,
+                preserve_paths,
+                exclude_uexp,
+                flat_names: Some(&flat_names),
+                jobs: jobs.map_or(0, usize::from),
+                progress: &progress,
+            };
+            let phase = Instant::now();
+            let results = batch::extract_assets(pak, &assets, &options)?;
+            progress.finish_and_clear();
+            timings.record("extract", phase);
+            
+            let mut filtered = 0;
+            let mut written = Written::since(started);
+            let mut not_found = HashSet::new();
+            for (asset_path, outcome) in results {
+                match outcome {
+                    batch::Outcome::Extracted(mut asset, failures) => {
+                        // Manifest paths are relative to the manifest, above the mount folder
+                        if !mount_dir.is_empty() {
+                            asset.uasset = format!("{}{}", mount_dir, asset.uasset);
+                            asset.uexp = asset.uexp.map(|uexp| format!("{}{}", mount_dir, uexp));
                         }
-                        Err(_) => None,
-                    };
-                    
-                    println!("OK ({} bytes)", uasset_data.len());
-                    
-                    manifest.extracted.push(ExtractedAsset {
-                        name: name.to_string(),
-                        pak_path: asset_path.to_string(),
-                        uasset: format!("{}.uasset", name),
-                        uexp: uexp_out,
-                    });
+                        written.add(&out_dir.join(&asset.uasset));
+                        if let Some(uexp) = &asset.uexp {
+                            written.add(&out_dir.join(uexp));
+                        }
+                        manifest.extracted.push(*asset);
+                        manifest.verification_failed.extend(failures);
+                    }
+                    batch::Outcome::Skipped => filtered += 1,
+                    batch::Outcome::Failed(error) => {
+                        if matches!(error, ExtractError::AssetNotFound(_)) {
+                            not_found.insert(asset_path.clone());
+                        }
+                        manifest.failed.push(FailedAsset { asset: asset_path, error: error.to_string() });
+                    }
                 }
+            }
+            
+            let extracted_count = manifest.extracted.len();
+            let failed_count = manifest.failed.len();
+            if failed_count > 0 {
+                let files = pak.files();
+                for failed in &manifest.failed {
+                    say!("\n  {} not extracted: {}", failed.asset, failed.error);
+                    if not_found.contains(&failed.asset) {
+                        suggest::print_suggestions(&failed.asset, &files);
+                    }
+                }
+            }
+            
+            // Write manifest
+            let phase = Instant::now();
+            let manifest_path = out_dir.join("manifest.json");
+            if append_manifest && manifest_path.exists() {
+                let existing = mt_pak_extract::read_manifest(&manifest_path)?;
+                info!("Appending to existing manifest ({} assets)", existing.extracted.len());
+                manifest = merge_manifests(existing, manifest);
+            }
+            if sort_manifest {
+                manifest.extracted.sort_by(|a, b| a.pak_path.cmp(&b.pak_path));
+                manifest.failed.sort_by(|a, b| a.asset.cmp(&b.asset));
+                manifest.collisions.sort_by(|a, b| a.asset.cmp(&b.asset));
+                manifest.verification_failed.sort_by(|a, b| a.entry.cmp(&b.entry));
+            }
+            let manifest_path = match manifest_format {
+                ManifestFormat::Json => {
+                    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+                    manifest_path
+                }
+                ManifestFormat::Csv => {
+                    let csv_path = out_dir.join("manifest.csv");
+                    mt_pak_extract::write_manifest_csv(&manifest, &csv_path)?;
+                    csv_path
+                }
+            };
+            timings.record("manifest", phase);
+            
+            say!("\n=== Extracted {} assets ===", extracted_count);
+            if failed_count > 0 {
+                let listed = if manifest_format == ManifestFormat::Json { "in the manifest" } else { "above" };
+                say!("Extraction failed: {} assets (listed {})", failed_count, listed);
+            }
+            if verify {
+                say!("Verification failed: {} entries", manifest.verification_failed.len());
+            }
+            if let Some(want) = encryption_filter {
+                say!("Skipped {} {} assets", filtered, if want { "plaintext" } else { "encrypted" });
+            }
+            say!("Manifest: {}", manifest_path.display());
+            say!("\nRun C# parser: cd csharp/CargoExtractor && dotnet run -- --batch");
+            written.log();
+            timings.log();
+            
+            if failed_count > 0 && !ignore_errors {
+                return Err(ExtractError::PartialFailure { failed: failed_count }.into());
+            }
+        }
+        
+        Command::Extract { all, prefix, ext, stream, skip, limit, jobs, out_layout, force, out, .. } if all || prefix.is_some() => {
+            let out_dir = &resolve_out_dir(out, &user_config)?;
+            let out_dir = &match out_layout {
+                OutLayout::Mount => out_dir.join(mount_layout_dir(paks.mount_points())?),
+                OutLayout::Internal => out_dir.clone(),
+            };
+            // Streaming needs each entry's location in the PAK, which the filter reads anyway
+            let phase = Instant::now();
+            let mut entries = if stream || encryption_filter.is_some() {
+                paks.entries(&aes_key)?
+            } else {
+                HashMap::new()
+            };
+            timings.record("index", phase);
+            
+            // Match whole folder names, so "DataAsset" doesn't pull in "DataAssetOld/..."
+            let folder = prefix.as_deref().map(|p| format!("{}/", normalize_slashes(p).trim_matches('/')));
+            let extensions: Vec<String> = ext.iter()
+                .map(|e| format!(".{}", e.trim_start_matches('.').to_lowercase()))
+                .collect();
+            let files: Vec<String> = paks.files().into_iter()
+                .filter(|f| folder.as_deref().is_none_or(|folder| f.starts_with(folder)))
+                .filter(|f| extensions.is_empty() || extensions.iter().any(|e| f.to_lowercase().ends_with(e)))
+                .collect();
+            if !extensions.is_empty() {
+                info!("Only {} entries", extensions.join(", "));
+            }
+            match &folder {
+                Some(folder) if files.is_empty() => return Err(ExtractError::AssetNotFound(format!("{}*", folder)).into()),
+                Some(folder) => info!("Extracting {} entries under {} to {}/", files.len(), folder, out_dir.display()),
+                None => info!("Extracting all {} entries to {}/", files.len(), out_dir.display()),
+            }
+            let files = window(files, skip, limit);
+            if skip > 0 || limit.is_some() {
+                info!("Taking {} of them (--skip {}, --limit {})", files.len(), skip, limit.map_or("none".to_string(), |l| l.to_string()));
+            }
+            fs::create_dir_all(out_dir)?;
+            let _lock = lock::OutputLock::acquire(out_dir, force)?;
+            
+            // Entries are decoded in parallel, each worker reading through its own
+            // handles on the PAKs; outcomes are reported afterwards in PAK order
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs.map_or(0, usize::from))
+                .build()?;
+            let progress = progress::Progress::new(files.len() as u64);
+            let phase = Instant::now();
+            let outcomes: Vec<EntryOutcome> = pool.install(|| {
+                files.par_iter()
+                    .map_init(
+                        || paks.open_readers(mmap),
+                        |readers, path| -> io::Result<EntryOutcome> {
+                            let readers = readers.as_mut().map_err(|e| io::Error::new(e.kind(), e.to_string()))?;
+                            let outcome = extract_listed_entry(&paks, readers, &entries, path, out_dir, stream, encryption_filter);
+                            let bytes = match &outcome {
+                                EntryOutcome::Written { out_path, .. } => fs::metadata(out_path).map_or(0, |m| m.len()),
+                                _ => 0,
+                            };
+                            progress.inc(bytes);
+                            Ok(outcome)
+                        },
+                    )
+                    .collect::<io::Result<_>>()
+            })?;
+            progress.finish_and_clear();
+            timings.record("extract", phase);
+            
+            let mut by_extension: BTreeMap<String, usize> = BTreeMap::new();
+            let mut written = Written::since(started);
+            let mut filtered = 0;
+            let mut failed = 0;
+            let mut by_pak: HashMap<usize, usize> = HashMap::new();
+            for (path, outcome) in files.iter().zip(outcomes) {
+                match outcome {
+                    EntryOutcome::Missing => {}
+                    EntryOutcome::Filtered => filtered += 1,
+                    EntryOutcome::Written { out_path, pak } => {
+                        written.add(&out_path);
+                        *by_pak.entry(pak).or_default() += 1;
+                        let extension = Path::new(path)
+                            .extension()
+                            .and_then(|e| e.to_str())
+                            .map(|e| format!(".{}", e))
+                            .unwrap_or_else(|| "(none)".to_string());
+                        *by_extension.entry(extension).or_default() += 1;
+                    }
+                    EntryOutcome::Failed(e) => {
+                        // Read the index on the first failure, to say how failed entries are stored
+                        if entries.is_empty() {
+                            let phase = Instant::now();
+                            entries = paks.entries(&aes_key)?;
+                            timings.record("index", phase);
+                        }
+                        error!("{}: {}", path, e.with_entry(entries.get(path)));
+                        failed += 1;
+                    }
+                }
+            }
+            
+            let extracted: usize = by_extension.values().sum();
+            say!("\n=== Extracted {} entries ({}) ===", extracted, tree::format_size(written.bytes));
+            for (extension, count) in &by_extension {
+                say!("  {:<10} {}", extension, count);
+            }
+            if paks.pak_count() > 1 {
+                say!("From:");
+                for (i, source) in paks.paths().enumerate() {
+                    say!("  {} {}", source.display(), by_pak.get(&i).unwrap_or(&0));
+                }
+            }
+            if failed > 0 {
+                say!("Extraction failed: {} entries", failed);
+            }
+            if let Some(want) = encryption_filter {
+                say!("Skipped {} {} entries", filtered, if want { "plaintext" } else { "encrypted" });
+            }
+            written.log();
+            timings.log();
+            
+            if failed > 0 && !ignore_errors {
+                return Err(ExtractError::PartialFailure { failed }.into());
+            }
+        }
+        
+        Command::Extract { asset, by_package, assume_mount, preserve_paths, exclude_uexp, ext, stream, skip, limit, jobs, out_layout, force, out, .. } => {
+            if stream {
+                return Err("--stream only applies to --all and --prefix".into());
+            }
+            if !ext.is_empty() {
+                return Err("--ext only applies to --all and --prefix".into());
+            }
+            if skip > 0 || limit.is_some() {
+                return Err("--skip and --limit only apply to --all and --prefix".into());
+            }
+            if jobs.is_some() {
+                return Err("--jobs only applies to --all, --prefix and --stdin".into());
+            }
+            let out_dir = resolve_out_dir(out, &user_config)?;
+            let (preserve_paths, out_dir) = match out_layout {
+                OutLayout::Mount => (true, out_dir.join(mount_layout_dir(paks.mount_points())?)),
+                OutLayout::Internal => (preserve_paths, out_dir),
+            };
+            let asset_path = match by_package {
+                Some(package_path) => {
+                    let mount_point = assume_mount.as_deref().unwrap_or(paks.mount_point());
+                    let internal = package::package_to_internal(&package_path, mount_point, &paks.files())?;
+                    info!("Package {} -> {} (mount point: {})", package_path, internal, mount_point);
+                    internal
+                }
+                None => asset.unwrap_or_default(),
+            };
+            
+            let asset = normalize_asset_path(&asset_path);
+            let asset_path = asset.as_str();
+            let uasset_path = asset.uasset();
+            
+            info!("Extracting: {}", uasset_path);
+            
+            if let Some(want) = encryption_filter
+                && let Some(&encrypted) = paks.encryption_flags(&aes_key)?.get(&uasset_path)
+                && encrypted != want
+            {
+                say!("Skipped: entry is {}", if encrypted { "encrypted" } else { "plaintext" });
+                return Ok(());
+            }
+            
+            let multiple_paks = paks.pak_count() > 1;
+            let phase = Instant::now();
+            let result = match paks.resolve(&uasset_path) {
+                Some((pak, file, source)) => {
+                    if multiple_paks {
+                        say!("From: {}", source.display());
+                    }
+                    fs::create_dir_all(&out_dir)?;
+                    let _lock = lock::OutputLock::acquire(&out_dir, force)?;
+                    mt_pak_extract::extract_asset(pak, file, asset_path, &out_dir, preserve_paths, exclude_uexp)
+                        .map_err(|e| mt_pak_extract::describe_read_error(e, file, &aes_key))
+                }
+                None => Err(ExtractError::AssetNotFound(uasset_path.clone())),
+            };
+            timings.record("extract", phase);
+            let asset = match result {
+                Ok(asset) => asset,
                 Err(e) => {
-                    println!("FAILED: {}", e);
+                    say!("  FAILED: {}", e);
+                    if let ExtractError::AssetNotFound(_) = e {
+                        suggest::print_suggestions(asset_path, &paks.files());
+                    }
+                    return Err(e.into());
+                }
+            };
+            
+            let mut written = Written::since(started);
+            let uasset_out = out_dir.join(&asset.uasset);
+            say!("Saved: {}", uasset_out.display());
+            say!("  uasset: {} bytes", asset.uasset_size);
+            written.add(&uasset_out);
+            match &asset.uexp {
+                Some(uexp) => {
+                    say!("Saved: {}", out_dir.join(uexp).display());
+                    if let Some(size) = asset.uexp_size {
+                        say!("  uexp: {} bytes", size);
+                    }
+                    written.add(&out_dir.join(uexp));
                 }
+                None if exclude_uexp => say!("  .uexp not extracted (--exclude-uexp)"),
+                None => say!("  No .uexp file"),
             }
+            written.log();
+            timings.log();
+            
+            say!("\nDone! Use the C# parser to extract properties:");
+            say!("  cd csharp/CargoExtractor && dotnet run -- {}", uasset_out.display());
         }
         
-        // Write manifest
-        let manifest_path = out_dir.join("manifest.json");
-        let manifest_json = serde_json::to_string_pretty(&manifest)?;
-        fs::write(&manifest_path, &manifest_json)?;
+        Command::ShowManifest { .. }
+        | Command::VerifyManifest { .. }
+        | Command::Split { .. }
+        | Command::Merge { .. }
+        | Command::Info { .. }
+        | Command::Diff { .. }
+        | Command::Selftest { .. }
+        | Command::StoreKey
+        | Command::List { .. }
+        | Command::Search { .. }
+        | Command::Stats
+        | Command::Tree { .. } => unreachable!("handled before opening the PAK"),
+    }
+    
+    Ok(())
+}
+
+/// list, search, stats and tree, answered from the PAKs' index alone
+fn list_index(command: Command, listing: &index_cache::Listing, ndjson: bool, encrypted: Option<bool>) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::List { count_only: true, all, .. } => {
+            say!("{}", listed_entries(&listing.files, &listing.entries, all, encrypted, None).len());
+        }
         
-        println!("\n=== Extracted {} assets ===", manifest.extracted.len());
-        println!("Manifest: {}", manifest_path.display());
-        println!("\nRun C# parser: cd csharp/CargoExtractor && dotnet run -- --batch");
+        Command::List { tree: true, all, skip, limit, .. } => {
+            let listed = listed_entries(&listing.files, &listing.entries, all, encrypted, None);
+            let total = listed.len();
+            let mut root = tree::TreeNode::default();
+            for entry in window(listed, skip, limit) {
+                root.insert(list_name(&entry.path, all), entry.uncompressed_size);
+            }
+            say!("=== {} ===", if all { "All entries" } else { "Available DataAsset files" });
+            root.print("", None);
+            say!("Total: {}{} {}, {}", root.files, of_total(root.files, total), if all { "entries" } else { "DataAsset files" }, tree::format_size(root.size));
+        }
         
-        return Ok(());
+        Command::Tree { depth, json } => {
+            let depth = depth.map(|d| d as usize);
+            let mut root = tree::TreeNode::default();
+            for (path, entry) in &listing.entries {
+                root.insert(path, entry.uncompressed_size);
+            }
+            if json {
+                say!("{}", serde_json::to_string_pretty(&root.to_json(depth))?);
+            } else {
+                say!("=== PAK contents ===");
+                root.print("", depth);
+                say!("Total: {} files, {}", root.files, tree::format_size(root.size));
+            }
+        }
+        
+        Command::Stats => {
+            stats::print_stats(listing.entries.values());
+        }
+        
+        Command::List { json: true, all, sort, skip, limit, .. } => {
+            let listed: Vec<ListedEntry> = window(listed_entries(&listing.files, &listing.entries, all, encrypted, sort), skip, limit).into_iter()
+                .map(|entry| ListedEntry {
+                    path: &entry.path,
+                    size: entry.uncompressed_size,
+                    compressed_size: entry.compressed_size,
+                    compression: entry.compression_name(),
+                    encrypted: entry.encrypted,
+                })
+                .collect();
+            say!("{}", serde_json::to_string_pretty(&listed)?);
+        }
+        
+        Command::List { all, skip, limit, .. } if ndjson => {
+            let mut out = io::stdout().lock();
+            for entry in window(listed_entries(&listing.files, &listing.entries, all, encrypted, None), skip, limit) {
+                serde_json::to_writer(&mut out, &ListEntry { path: &entry.path })?;
+                writeln!(out)?;
+            }
+        }
+        
+        Command::List { all, sort, skip, limit, .. } => {
+            let listed = listed_entries(&listing.files, &listing.entries, all, encrypted, sort);
+            let total = listed.len();
+            let listed = window(listed, skip, limit);
+            
+            say!("=== {} ===", if all { "All entries" } else { "Available DataAsset files" });
+            let name_width = listed.iter().map(|e| list_name(&e.path, all).len()).max().unwrap_or(4).max(4);
+            say!("  {:<name_width$}  {:>10}  {:>10}  COMPRESSION", "NAME", "SIZE", "COMPRESSED");
+            for entry in &listed {
+                say!(
+                    "  {:<name_width$}  {:>10}  {:>10}  {}",
+                    list_name(&entry.path, all),
+                    tree::format_size(entry.uncompressed_size),
+                    tree::format_size(entry.compressed_size),
+                    entry.compression_name(),
+                );
+            }
+            say!("Total: {}{} {}", listed.len(), of_total(listed.len(), total), if all { "entries" } else { "DataAsset files" });
+        }
+        
+        Command::Search { pattern, regex, case_sensitive, skip, limit, count_only } => {
+            let (pattern, matcher) = match (pattern, regex) {
+                (_, Some(regex)) => {
+                    let matcher = search::PathMatcher::regex(&regex)
+                        .map_err(|e| format!("Invalid regex '{}': {}", regex, e))?;
+                    (regex, matcher)
+                }
+                (pattern, None) => {
+                    let pattern = pattern.unwrap_or_default();
+                    let matcher = search::PathMatcher::new(&pattern, case_sensitive)
+                        .map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?;
+                    (pattern, matcher)
+                }
+            };
+            let matches: Vec<&String> = listing.files.iter()
+                .filter(|path| path.ends_with(".uasset") && matcher.is_match(path))
+                .collect();
+            let total = matches.len();
+            if count_only {
+                say!("{}", total);
+                return Ok(());
+            }
+            let matches = window(matches, skip, limit);
+            
+            if ndjson {
+                let mut out = io::stdout().lock();
+                for path in &matches {
+                    serde_json::to_writer(&mut out, &ListEntry { path })?;
+                    writeln!(out)?;
+                }
+                return Ok(());
+            }
+            
+            match matcher {
+                search::PathMatcher::Substring { .. } => say!("=== Searching for assets containing '{}' ===", pattern),
+                search::PathMatcher::Glob(_) => say!("=== Searching for assets matching '{}' ===", pattern),
+                search::PathMatcher::Regex(_) => say!("=== Searching for assets matching /{}/ ===", pattern),
+            }
+            let mut total_size = 0;
+            for path in &matches {
+                let asset_path = normalize_asset_path(path);
+                say!("  {}", asset_path);
+                total_size += [asset_path.uasset(), asset_path.uexp()].iter()
+                    .filter_map(|entry_path| listing.entries.get(entry_path))
+                    .map(|entry| entry.uncompressed_size)
+                    .sum::<u64>();
+            }
+            say!("Total: {}{} matching assets, {}", matches.len(), of_total(matches.len(), total), tree::format_size(total_size));
+        }
+        
+        _ => unreachable!("only called for list, search, stats and tree"),
     }
     
-    // Single asset mode (existing behavior)
-    let asset_path = args.iter()
-        .skip(1)
-        .find(|a| !a.starts_with("--"))
-        .cloned()
-        .unwrap_or_else(|| "MotorTown/Content/DataAsset/Cargos".to_string());
+    Ok(())
+}
+
+/// What happened to one entry of `extract --all`/`--prefix`
+enum EntryOutcome {
+    Written { out_path: PathBuf, pak: usize },
+    /// Left out by --only-encrypted/--only-plaintext
+    Filtered,
+    /// Listed, but no PAK provides it
+    Missing,
+    Failed(ExtractError),
+}
+
+/// Extract one entry of `extract --all`/`--prefix` through a worker's own `readers`.
+/// `entries` is only needed for streaming and the encryption filter.
+fn extract_listed_entry(
+    paks: &pak_set::PakSet,
+    readers: &mut [PakFile],
+    entries: &HashMap<String, EntryInfo>,
+    path: &str,
+    out_dir: &Path,
+    stream: bool,
+    encryption_filter: Option<bool>,
+) -> EntryOutcome {
+    if let Some(want) = encryption_filter
+        && entries.get(path).is_some_and(|entry| entry.encrypted != want)
+    {
+        return EntryOutcome::Filtered;
+    }
+    let Some((i, pak)) = paks.owner(path) else {
+        return EntryOutcome::Missing;
+    };
+    let reader = &mut readers[i];
+    let result = match entries.get(path) {
+        Some(entry) if stream => mt_pak_extract::stream_entry(pak, reader, entry, out_dir),
+        _ => mt_pak_extract::extract_entry(pak, reader, path, out_dir),
+    };
+    match result {
+        Ok(out_path) => EntryOutcome::Written { out_path, pak: i },
+        Err(e) => EntryOutcome::Failed(e),
+    }
+}
+
+/// Files written by an extraction, for the summary every mode logs when it's done
+struct Written {
+    files: usize,
+    bytes: u64,
+    started: Instant,
+}
+
+impl Written {
+    fn since(started: Instant) -> Self {
+        Written { files: 0, bytes: 0, started }
+    }
     
-    let asset_path = asset_path
-        .trim_end_matches(".uasset")
-        .trim_end_matches(".uexp")
-        .to_string();
+    /// Count a written file at its size on disk
+    fn add(&mut self, path: &Path) {
+        let bytes = fs::metadata(path).map_or(0, |m| m.len());
+        self.files += 1;
+        self.bytes += bytes;
+        output::record_file(path, bytes);
+    }
     
-    let uasset_path = format!("{}.uasset", asset_path);
-    let uexp_path = format!("{}.uexp", asset_path);
+    fn log(&self) {
+        let elapsed = self.started.elapsed();
+        let rate = self.bytes as f64 / elapsed.as_secs_f64().max(0.001);
+        info!("Wrote {} files, {} in {:.2?} ({}/s)", self.files, tree::format_size(self.bytes), elapsed, tree::format_size(rate as u64));
+    }
+}
+
+/// How long each phase of a command took, for `-vv`: whether the time goes to opening
+/// the PAK, parsing its index or extracting
+#[derive(Default)]
+struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    /// Add the time since `since` to `phase`, which may run more than once
+    fn record(&mut self, phase: &'static str, since: Instant) {
+        let elapsed = since.elapsed();
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => self.phases.push((phase, elapsed)),
+        }
+    }
     
-    println!("Extracting: {}", uasset_path);
+    fn log(&self) {
+        let phases: Vec<String> = self.phases.iter()
+            .map(|(phase, elapsed)| format!("{} {:.2?}", phase, elapsed))
+            .collect();
+        trace!("Time per phase: {}", phases.join(", "));
+    }
+}
+
+/// Folder under the output directory that `--out-layout mount` writes entries to.
+/// PAKs read together must share their mount point, or their entries wouldn't line up.
+fn mount_layout_dir<'a>(mut mount_points: impl Iterator<Item = &'a str>) -> Result<String, Box<dyn std::error::Error>> {
+    let first = mount_points.next().unwrap_or_default();
+    if let Some(other) = mount_points.find(|mount_point| *mount_point != first) {
+        return Err(format!("--out-layout mount needs PAKs with the same mount point, not {} and {}", first, other).into());
+    }
+    Ok(mt_pak_extract::mount_layout_dir(first)?)
+}
+
+/// Output directory: --out, then the user config's out_dir, then out/.
+/// Not created here, so dry runs leave the disk alone.
+fn resolve_out_dir(out: Option<PathBuf>, user_config: &user_config::UserConfig) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let out_dir = out
+        .or_else(|| user_config.out_dir.as_ref().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("out"));
+    if out_dir.exists() && !out_dir.is_dir() {
+        return Err(format!("Output path {} exists and is not a directory", out_dir.display()).into());
+    }
+    Ok(out_dir)
+}
+
+/// Parse a batch config as JSON, YAML or TOML, chosen by file extension
+fn load_config(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let config = match extension.as_str() {
+        "json" => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+        _ => {
+            return Err(format!(
+                "Unsupported config format '{}' (expected .json, .yaml, .yml or .toml)",
+                path.display(),
+            ).into());
+        }
+    };
+    Ok(config.map_err(|e| format!("Invalid config {}: {}", path.display(), e))?)
+}
+
+/// The explicit assets in config order, then the assets matched by the
+/// include/exclude globs (against the path without `.uasset`) in PAK order
+fn resolve_assets(config: &Config, files: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let globs = |patterns: &[String]| {
+        patterns.iter()
+            .map(|p| search::PathMatcher::glob(p, false).map_err(|e| format!("Invalid glob '{}' in config: {}", p, e)))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let include = globs(&config.include)?;
+    let exclude = globs(&config.exclude)?;
     
-    let uasset_data = pak.get(&uasset_path, &mut file)?;
-    let uexp_data = match pak.get(&uexp_path, &mut file) {
-        Ok(data) => {
-            println!("  uexp: {} bytes", data.len());
-            Some(data)
+    let mut assets = config.assets.clone();
+    let mut seen: HashSet<AssetPath> = assets.iter().map(|a| normalize_asset_path(a)).collect();
+    for path in files.iter().filter(|path| path.ends_with(".uasset")) {
+        let matches = |globs: &[search::PathMatcher]| globs.iter().any(|glob| glob.is_match(path));
+        let asset = normalize_asset_path(path);
+        if matches(&include) && !matches(&exclude) && seen.insert(asset.clone()) {
+            assets.push(asset.to_string());
         }
-        Err(_) => {
-            println!("  No .uexp file");
-            None
+    }
+    Ok(assets)
+}
+
+/// The assets of every config in `--config` order, each asset once where it was
+/// first seen. An asset in more than one config is reported, since the configs
+/// are meant to split the list between them.
+fn merge_config_assets(per_config: Vec<(&Path, Vec<String>)>) -> Vec<String> {
+    let mut assets = Vec::new();
+    let mut first_seen: HashMap<AssetPath, &Path> = HashMap::new();
+    for (config_path, config_assets) in per_config {
+        for asset in config_assets {
+            let normalized = normalize_asset_path(&asset);
+            match first_seen.get(&normalized) {
+                None => {
+                    first_seen.insert(normalized, config_path);
+                    assets.push(asset);
+                }
+                Some(&first) if first != config_path => {
+                    warn!("{} is in both {} and {}; extracting it once", asset, first.display(), config_path.display());
+                }
+                Some(_) => {}
+            }
         }
+    }
+    assets
+}
+
+/// Asset paths, one per line, as `list --format ndjson | jq -r .path` prints them.
+/// Blank lines are skipped, and an asset's .uasset and .uexp lines count once.
+fn read_asset_list(input: impl io::BufRead) -> io::Result<Vec<String>> {
+    let mut assets = Vec::new();
+    let mut seen = HashSet::new();
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && seen.insert(normalize_asset_path(line)) {
+            assets.push(line.to_string());
+        }
+    }
+    Ok(assets)
+}
+
+/// Combine a previous manifest with a new run. Assets are keyed by `pak_path`
+/// and entries from the new run replace older ones.
+fn merge_manifests(existing: Manifest, new: Manifest) -> Manifest {
+    let replaced: HashSet<&str> = new.extracted.iter().map(|a| a.pak_path.as_str()).collect();
+    let is_replaced = |entry: &str| {
+        replaced.contains(normalize_asset_path(entry).as_str())
     };
     
-    println!("  uasset: {} bytes", uasset_data.len());
+    let mut extracted: Vec<ExtractedAsset> = existing.extracted.into_iter()
+        .filter(|a| !replaced.contains(a.pak_path.as_str()))
+        .collect();
+    let mut verification_failed: Vec<VerificationFailure> = existing.verification_failed.into_iter()
+        .filter(|f| !is_replaced(&f.entry))
+        .collect();
+    // A failure is resolved by a later extraction, or replaced by a later failure
+    let mut failed: Vec<FailedAsset> = existing.failed.into_iter()
+        .filter(|f| !replaced.contains(f.asset.as_str()) && !new.failed.iter().any(|n| n.asset == f.asset))
+        .collect();
+    let mut collisions: Vec<CollisionRecord> = existing.collisions.into_iter()
+        .filter(|c| !replaced.contains(c.asset.as_str()) && !new.collisions.iter().any(|n| n.asset == c.asset))
+        .collect();
     
-    let output_name = Path::new(&asset_path)
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("asset");
+    extracted.extend(new.extracted);
+    failed.extend(new.failed);
+    collisions.extend(new.collisions);
+    verification_failed.extend(new.verification_failed);
     
-    fs::write(format!("{}.uasset", output_name), &uasset_data)?;
-    println!("Saved: {}.uasset", output_name);
-    
-    if let Some(uexp) = uexp_data {
-        fs::write(format!("{}.uexp", output_name), &uexp)?;
-        println!("Saved: {}.uexp", output_name);
+    Manifest { version: MANIFEST_VERSION, extracted, failed, collisions, verification_failed }
+}
+
+/// `list` shows DataAsset .uasset files, or every entry with --all
+fn is_listed(path: &str, all: bool) -> bool {
+    all || (path.ends_with(".uasset") && path.contains("DataAsset"))
+}
+
+/// Index entries for `list`, in PAK order unless sorted
+fn listed_entries<'a>(
+    files: &[String],
+    entries: &'a HashMap<String, EntryInfo>,
+    all: bool,
+    encrypted: Option<bool>,
+    sort: Option<ListSort>,
+) -> Vec<&'a EntryInfo> {
+    let mut listed: Vec<&EntryInfo> = files.iter()
+        .filter(|path| is_listed(path, all))
+        .filter_map(|path| entries.get(path))
+        .filter(|entry| encrypted.is_none_or(|want| entry.encrypted == want))
+        .collect();
+    match sort {
+        Some(ListSort::Name) => listed.sort_by(|a, b| a.path.cmp(&b.path)),
+        Some(ListSort::Size) => listed.sort_by_key(|e| Reverse(e.uncompressed_size)),
+        None => {}
     }
-    
-    println!("\nDone! Use the C# parser to extract properties:");
-    println!("  cd csharp/CargoExtractor && dotnet run -- {}.uasset", output_name);
-    
-    Ok(())
+    listed
+}
+
+/// Items `skip..skip + limit` of an already filtered and sorted listing
+fn window<T>(items: Vec<T>, skip: usize, limit: Option<usize>) -> Vec<T> {
+    items.into_iter().skip(skip).take(limit.unwrap_or(usize::MAX)).collect()
+}
+
+/// " of <total>" when a listing was cut down by --skip/--limit
+fn of_total(shown: usize, total: usize) -> String {
+    if shown == total { String::new() } else { format!(" of {}", total) }
+}
+
+/// Listed assets are shown without `.uasset`; with --all every entry keeps its extension
+fn list_name(path: &str, all: bool) -> &str {
+    if all { path } else { path.trim_end_matches(".uasset") }
 }
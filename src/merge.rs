@@ -0,0 +1,96 @@
+//! Merge several PAKs into one, later PAKs overriding earlier ones like the game's load order.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use aes::Aes256;
+use log::{info, warn};
+use repak::PakBuilder;
+
+use crate::index::{self, EntryInfo};
+use crate::say;
+
+pub fn merge_paks(inputs: &[PathBuf], output: &Path, key: &Aes256) -> Result<(), Box<dyn std::error::Error>> {
+    // Creating the output truncates it, which would destroy an input that is still being read
+    if let Ok(output_path) = output.canonicalize() {
+        for input in inputs {
+            if input.canonicalize().is_ok_and(|input_path| input_path == output_path) {
+                return Err(format!("--output {} is also an input; write the merged PAK somewhere else", output.display()).into());
+            }
+        }
+    }
+    
+    let mut paks = Vec::new();
+    let mut readers = Vec::new();
+    let mut indexes = Vec::new();
+    for input in inputs {
+        info!("Reading {}", input.display());
+        let (pak, mut reader) = crate::open_pak(input, key)?;
+        indexes.push(index::read_index(&mut reader, Some(key))?);
+        paks.push(pak);
+        readers.push(reader);
+    }
+    
+    // Internal path -> (input, entry) that provides it, in the order paths were first seen
+    let mut winners: HashMap<&str, (usize, &EntryInfo)> = HashMap::new();
+    let mut order = Vec::new();
+    let mut conflicts = Vec::new();
+    for (i, pak_index) in indexes.iter().enumerate() {
+        for entry in &pak_index.entries {
+            match winners.insert(&entry.path, (i, entry)) {
+                Some((previous, _)) => conflicts.push((entry.path.as_str(), previous, i)),
+                None => order.push(entry.path.as_str()),
+            }
+        }
+    }
+    
+    // The first input sets the layout, as it does for the game
+    let base = &paks[0];
+    for (input, pak) in inputs.iter().zip(&paks).skip(1) {
+        if pak.version() != base.version() || pak.mount_point() != base.mount_point() {
+            warn!("{} is {:?} mounted at {}; the merged PAK uses {:?} mounted at {}",
+                input.display(), pak.version(), pak.mount_point(), base.version(), base.mount_point());
+        }
+    }
+    
+    // The writer compresses every compressed entry with one method, so refuse to merge
+    // inputs that would need several rather than recompressing some of them
+    let mut methods: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, entry) in winners.values() {
+        if let Some(method) = &entry.compression {
+            methods.entry(method.as_str()).or_insert(*i);
+        }
+    }
+    if methods.len() > 1 {
+        let used: Vec<String> = methods.iter()
+            .map(|(method, i)| format!("{} (e.g. {})", method, inputs[*i].display()))
+            .collect();
+        return Err(format!("inputs mix compression methods {}; a merged PAK can only use one", used.join(", ")).into());
+    }
+    let compression = methods.keys().next().map(|method| crate::parse_compression(method)).transpose()?;
+    
+    info!("Merging {} entries from {} PAKs into {}", order.len(), inputs.len(), output.display());
+    let mut writer = PakBuilder::new()
+        .compression(compression)
+        .writer(BufWriter::new(File::create(output)?), base.version(), base.mount_point().to_string(), base.path_hash_seed());
+    for path in &order {
+        let (i, entry) = winners[path];
+        let data = paks[i].get(path, &mut readers[i])?;
+        writer.write_file(path, entry.compression.is_some(), data)?;
+    }
+    writer.write_index()?.flush()?;
+    
+    if !conflicts.is_empty() {
+        say!("\n=== Overridden entries ({}) ===", conflicts.len());
+        for (path, previous, winner) in &conflicts {
+            say!("  {}: {} overridden by {}", path, inputs[*previous].display(), inputs[*winner].display());
+        }
+    }
+    
+    let size = fs::metadata(output)?.len();
+    say!("\n=== Merged {} entries into {} ({} bytes) ===", order.len(), output.display(), size);
+    
+    Ok(())
+}
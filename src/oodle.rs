@@ -0,0 +1,100 @@
+//! Oodle decompression through the game's own `oo2core` library, loaded at runtime
+//! since the Oodle SDK can't be shipped with this tool. Enabled by the `oodle` feature.
+//!
+//! The library comes from `--oodle <lib>`, else the `OODLE_LIB` environment variable,
+//! e.g. `Engine/Binaries/ThirdParty/Oodle/Win64/oo2core_9_win64.dll` in the game folder.
+//! It is only loaded once repak meets the first Oodle-compressed block.
+
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::OnceLock;
+
+use libloading::Library;
+
+pub const LIBRARY_ENV: &str = "OODLE_LIB";
+
+static LIBRARY_PATH: OnceLock<PathBuf> = OnceLock::new();
+static DECOMPRESS: OnceLock<Result<OodleLzDecompress, String>> = OnceLock::new();
+
+/// `OodleLZ_Decompress`, the same in every oo2core version
+type OodleLzDecompress = unsafe extern "C" fn(
+    comp_buf: *const u8,
+    comp_buf_size: isize,
+    raw_buf: *mut u8,
+    raw_len: isize,
+    fuzz_safe: i32,
+    check_crc: i32,
+    verbosity: i32,
+    dec_buf_base: *mut u8,
+    dec_buf_size: isize,
+    callback: *const c_void,
+    callback_user_data: *const c_void,
+    decoder_memory: *mut u8,
+    decoder_memory_size: isize,
+    thread_phase: i32,
+) -> isize;
+
+/// What repak calls to decompress a block: returns the bytes written, 0 on failure
+type Decompress = fn(&[u8], &mut [u8]) -> i32;
+
+/// Use the library at `path` instead of `OODLE_LIB`
+pub fn set_library(path: &Path) {
+    let _ = LIBRARY_PATH.set(path.to_path_buf());
+}
+
+fn library_path() -> Option<PathBuf> {
+    LIBRARY_PATH.get().cloned().or_else(|| std::env::var_os(LIBRARY_ENV).map(PathBuf::from))
+}
+
+/// Look up `OodleLZ_Decompress`. The library stays loaded until the process exits.
+fn load() -> Result<OodleLzDecompress, String> {
+    let path = library_path().ok_or_else(|| {
+        format!("Entry is Oodle-compressed, but no Oodle library is set; pass --oodle <oo2core library> or set {}", LIBRARY_ENV)
+    })?;
+    // SAFETY: loading runs the library's initializers, which oo2core has no requirements for
+    let library = unsafe { Library::new(&path) }
+        .map_err(|e| format!("Could not load Oodle library {}: {}", path.display(), e))?;
+    let library: &'static Library = Box::leak(Box::new(library));
+    // SAFETY: the symbol has the `OodleLzDecompress` signature
+    let decompress = unsafe { library.get::<OodleLzDecompress>(b"OodleLZ_Decompress\0") }
+        .map_err(|e| format!("{} is not an Oodle library: {}", path.display(), e))?;
+    log::debug!("Loaded Oodle from {}", path.display());
+    Ok(*decompress)
+}
+
+/// The `Decompress` handed to repak
+fn decompress(comp_buf: &[u8], raw_buf: &mut [u8]) -> i32 {
+    let Some(Ok(decompress)) = DECOMPRESS.get() else {
+        return 0;
+    };
+    // SAFETY: both buffers are valid for the lengths passed, and Oodle allocates its
+    // own decoder memory when none is given
+    let written = unsafe {
+        decompress(
+            comp_buf.as_ptr(),
+            comp_buf.len() as isize,
+            raw_buf.as_mut_ptr(),
+            raw_buf.len() as isize,
+            1,
+            0,
+            0,
+            ptr::null_mut(),
+            0,
+            ptr::null(),
+            ptr::null(),
+            ptr::null_mut(),
+            0,
+            3,
+        )
+    };
+    written as i32
+}
+
+/// Handed to repak, which asks for the decompressor when it first needs one
+pub(crate) fn decompressor() -> Result<Decompress, Box<dyn std::error::Error>> {
+    match DECOMPRESS.get_or_init(load) {
+        Ok(_) => Ok(decompress),
+        Err(e) => Err(e.clone().into()),
+    }
+}
@@ -0,0 +1,102 @@
+//! `--output-format json`: one JSON result object on stdout for tools such as the
+//! C# parser, with the files written, the errors logged and the time taken. The
+//! human-readable lines printed with `say!` go to stderr instead, next to the log.
+
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use log::{Level, Log, Metadata, Record};
+use serde::Serialize;
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static REPORT: Mutex<Report> = Mutex::new(Report { files: Vec::new(), bytes: 0, errors: Vec::new() });
+
+/// Print a line of human-readable output: to stdout, or to stderr with `--output-format json`
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// What the run did so far, collected for the result object
+struct Report {
+    files: Vec<String>,
+    bytes: u64,
+    errors: Vec<String>,
+}
+
+/// The result object printed by `print_result`
+#[derive(Serialize)]
+struct RunResult<'a> {
+    ok: bool,
+    exit_code: u8,
+    /// Files written, in the order they were written
+    files: &'a [String],
+    bytes: u64,
+    /// Every error logged, e.g. assets that failed to extract, and the one that ended the run
+    errors: &'a [String],
+    elapsed_ms: u128,
+}
+
+fn report() -> std::sync::MutexGuard<'static, Report> {
+    REPORT.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Count a file written to disk
+pub fn record_file(path: &Path, bytes: u64) {
+    if is_json() {
+        let mut report = report();
+        report.files.push(path.display().to_string());
+        report.bytes += bytes;
+    }
+}
+
+/// Print the result object to stdout
+pub fn print_result(exit_code: u8, elapsed: Duration) -> serde_json::Result<()> {
+    let report = report();
+    let result = RunResult {
+        ok: exit_code == 0,
+        exit_code,
+        files: &report.files,
+        bytes: report.bytes,
+        errors: &report.errors,
+        elapsed_ms: elapsed.as_millis(),
+    };
+    println!("{}", serde_json::to_string(&result)?);
+    Ok(())
+}
+
+/// The env_logger logger, also collecting error records for the result object
+pub(crate) struct ReportingLogger(pub(crate) env_logger::Logger);
+
+impl Log for ReportingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+    
+    fn log(&self, record: &Record) {
+        if is_json() && record.level() == Level::Error && self.0.matches(record) {
+            report().errors.push(record.args().to_string());
+        }
+        self.0.log(record);
+    }
+    
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
@@ -0,0 +1,107 @@
+//! Translation from UE package paths (`/Game/...`) to PAK internal paths.
+
+use crate::asset_path::normalize_slashes;
+
+/// Translate a package path like `/Game/DataAsset/Cargos` into the path used by
+/// `pak.files()`, which is relative to the PAK mount point.
+///
+/// `/Game/` maps to `<Project>/Content/` and `/Engine/` to `Engine/Content/`. The
+/// project name comes from the mount point if it names a content folder, otherwise
+/// from the first `<Project>/Content/` entry in the PAK.
+pub fn package_to_internal(package_path: &str, mount_point: &str, files: &[String]) -> Result<String, String> {
+    let mount = mount_relative(mount_point);
+    
+    let package_path = normalize_slashes(package_path);
+    let package_path = package_path.trim_start_matches('/');
+    let (root, rest) = package_path.split_once('/')
+        .ok_or_else(|| format!("Not a package path: /{}", package_path))?;
+    
+    let full_path = match root {
+        "Game" => format!("{}/Content/{}", project_name(&mount, files)?, rest),
+        "Engine" => format!("Engine/Content/{}", rest),
+        _ => return Err(format!("Unsupported package root '/{}/' (expected /Game/ or /Engine/)", root)),
+    };
+    
+    full_path.strip_prefix(mount.as_str())
+        .map(|p| p.to_string())
+        .ok_or_else(|| format!(
+            "/{} resolves to {}, which is outside the mount point {}",
+            package_path, full_path, mount_point
+        ))
+}
+
+/// Mount point relative to the engine root, e.g. `../../../MotorTown/Content/` -> `MotorTown/Content/`
+pub fn mount_relative(mount_point: &str) -> String {
+    let mut mount = normalize_slashes(mount_point);
+    while let Some(rest) = mount.strip_prefix("../") {
+        mount = rest.to_string();
+    }
+    let mount = mount.trim_start_matches('/');
+    if mount.is_empty() || mount.ends_with('/') {
+        mount.to_string()
+    } else {
+        format!("{}/", mount)
+    }
+}
+
+fn project_name(mount: &str, files: &[String]) -> Result<String, String> {
+    if let Some((project, _)) = mount.split_once("/Content/") {
+        return Ok(project.to_string());
+    }
+    
+    files.iter()
+        .filter_map(|f| format!("{}{}", mount, f).split_once("/Content/").map(|(p, _)| p.to_string()))
+        .find(|p| p != "Engine" && !p.contains('/'))
+        .ok_or_else(|| "Could not determine the project name from the PAK contents; try --assume-mount".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+    
+    #[test]
+    fn roots_under_an_engine_root_mount() {
+        let files = files(&["MotorTown/Content/DataAsset/Cargos.uasset", "Engine/Content/Functions/Math.uasset"]);
+        assert_eq!(package_to_internal("/Game/DataAsset/Cargos", "../../../", &files).unwrap(), "MotorTown/Content/DataAsset/Cargos");
+        assert_eq!(package_to_internal("/Engine/Functions/Math", "../../../", &files).unwrap(), "Engine/Content/Functions/Math");
+    }
+    
+    #[test]
+    fn roots_under_a_content_mount() {
+        let files = files(&["DataAsset/Cargos.uasset"]);
+        let mount = "../../../MotorTown/Content/";
+        assert_eq!(package_to_internal("/Game/DataAsset/Cargos", mount, &files).unwrap(), "DataAsset/Cargos");
+        let err = package_to_internal("/Engine/Functions/Math", mount, &files).unwrap_err();
+        assert!(err.contains("outside the mount point"), "{}", err);
+    }
+    
+    #[test]
+    fn assumed_mount_replaces_the_detected_one() {
+        // Entries of a content-mounted PAK don't name the project, so the detected
+        // engine-root mount can't resolve /Game/ ...
+        let files = files(&["DataAsset/Cargos.uasset"]);
+        let err = package_to_internal("/Game/DataAsset/Cargos", "../../../", &files).unwrap_err();
+        assert!(err.contains("--assume-mount"), "{}", err);
+        // ... but the mount given with --assume-mount does
+        assert_eq!(package_to_internal("/Game/DataAsset/Cargos", "../../../MotorTown/Content/", &files).unwrap(), "DataAsset/Cargos");
+    }
+    
+    #[test]
+    fn unsupported_package_paths() {
+        let files = files(&["MotorTown/Content/DataAsset/Cargos.uasset"]);
+        assert!(package_to_internal("/Plugin/Foo", "../../../", &files).unwrap_err().contains("Unsupported package root"));
+        assert!(package_to_internal("Cargos", "../../../", &files).unwrap_err().contains("Not a package path"));
+    }
+    
+    #[test]
+    fn mount_relative_to_engine_root() {
+        assert_eq!(mount_relative("../../../"), "");
+        assert_eq!(mount_relative("../../../MotorTown/Content/"), "MotorTown/Content/");
+        assert_eq!(mount_relative("..\\..\\..\\MotorTown\\Content"), "MotorTown/Content/");
+        assert_eq!(mount_relative("/MotorTown/"), "MotorTown/");
+    }
+}
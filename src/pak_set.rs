@@ -0,0 +1,114 @@
+//! Several PAKs read as one, the way the game layers `pakchunk0`, `pakchunk1`, ...
+//! and mod PAKs: an entry in a later PAK shadows the same path in earlier ones.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use aes::Aes256;
+
+use crate::container::Container;
+use crate::error::ExtractError;
+use crate::index::{self, EntryInfo};
+use crate::pak_file::PakFile;
+
+pub struct PakSet {
+    paks: Vec<OpenPak>,
+    /// Every internal path, in the order first seen
+    files: Vec<String>,
+    /// Internal path -> index in `paks` of the PAK that provides it
+    owners: HashMap<String, usize>,
+}
+
+struct OpenPak {
+    path: PathBuf,
+    pak: Box<dyn Container>,
+    reader: PakFile,
+}
+
+impl PakSet {
+    /// Open PAKs in load order, later ones overriding earlier ones. `paths` must not be empty.
+    /// With `mmap`, each PAK is memory-mapped instead of read through a buffer.
+    pub fn open(paths: &[PathBuf], key: &Aes256, mmap: bool) -> Result<Self, ExtractError> {
+        let mut paks = Vec::new();
+        let mut files = Vec::new();
+        let mut owners = HashMap::new();
+        for (i, path) in paths.iter().enumerate() {
+            let (pak, reader) = crate::open_pak_file(path, key, mmap)?;
+            for file in pak.files() {
+                if owners.insert(file.clone(), i).is_none() {
+                    files.push(file);
+                }
+            }
+            paks.push(OpenPak { path: path.clone(), pak: Box::new(pak), reader });
+        }
+        Ok(PakSet { paks, files, owners })
+    }
+    
+    pub fn pak_count(&self) -> usize {
+        self.paks.len()
+    }
+    
+    /// PAK files in load order
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.paks.iter().map(|open| open.path.as_path())
+    }
+    
+    /// The first PAK and its reader, for commands that read a single PAK
+    pub fn base(&mut self) -> (&dyn Container, &mut PakFile) {
+        let open = &mut self.paks[0];
+        (open.pak.as_ref(), &mut open.reader)
+    }
+    
+    /// Mount point of the first PAK
+    pub fn mount_point(&self) -> &str {
+        self.paks[0].pak.mount_point()
+    }
+    
+    /// Mount point of every PAK, in load order
+    pub fn mount_points(&self) -> impl Iterator<Item = &str> {
+        self.paks.iter().map(|open| open.pak.mount_point())
+    }
+    
+    /// Internal paths across all PAKs, each listed once
+    pub fn files(&self) -> Vec<String> {
+        self.files.clone()
+    }
+    
+    /// The PAK that provides `path` (the last one containing it), with its reader and file path
+    pub fn resolve(&mut self, path: &str) -> Option<(&dyn Container, &mut PakFile, &Path)> {
+        let open = &mut self.paks[*self.owners.get(path)?];
+        Some((open.pak.as_ref(), &mut open.reader, &open.path))
+    }
+    
+    /// Position in load order and container of the PAK that provides `path`, for
+    /// worker threads reading through their own handles from `open_readers`
+    pub fn owner(&self, path: &str) -> Option<(usize, &dyn Container)> {
+        let i = *self.owners.get(path)?;
+        Some((i, self.paks[i].pak.as_ref()))
+    }
+    
+    /// A new handle on every PAK, in load order, for one worker thread
+    pub fn open_readers(&self, mmap: bool) -> io::Result<Vec<PakFile>> {
+        self.paks.iter().map(|open| PakFile::open(&open.path, mmap)).collect()
+    }
+    
+    /// Index entry of every internal path, from the PAK that provides it
+    pub fn entries(&mut self, key: &Aes256) -> io::Result<HashMap<String, EntryInfo>> {
+        let mut entries = HashMap::new();
+        for (i, open) in self.paks.iter_mut().enumerate() {
+            let pak_index = index::read_index(&mut open.reader, Some(key))?;
+            for entry in pak_index.entries {
+                if self.owners.get(&entry.path) == Some(&i) {
+                    entries.insert(entry.path.clone(), entry);
+                }
+            }
+        }
+        Ok(entries)
+    }
+    
+    /// Map of internal path -> whether the entry data is encrypted, in the PAK that provides it
+    pub fn encryption_flags(&mut self, key: &Aes256) -> io::Result<HashMap<String, bool>> {
+        Ok(self.entries(key)?.into_iter().map(|(path, entry)| (path, entry.encrypted)).collect())
+    }
+}
@@ -0,0 +1,43 @@
+//! Progress bars for long extractions. indicatif hides them when stderr isn't
+//! a terminal, so piped and CI output stays clean.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::tree::format_size;
+
+/// Bar counting items, with the bytes they extracted per second and an ETA.
+/// The bar itself moves per item, since the sizes aren't known up front.
+pub struct Progress {
+    bar: ProgressBar,
+    bytes: AtomicU64,
+}
+
+impl Progress {
+    /// Bar for `len` items
+    pub fn new(len: u64) -> Self {
+        let style = ProgressStyle::with_template("[{elapsed_precise}] {wide_bar} {pos}/{len} ({msg}, ETA {eta})")
+            .expect("progress template is valid");
+        let bar = ProgressBar::new(len).with_style(style);
+        bar.set_message(format!("{}/s", format_size(0)));
+        Progress { bar, bytes: AtomicU64::new(0) }
+    }
+    
+    /// Count one item done, which extracted `bytes`
+    pub fn inc(&self, bytes: u64) {
+        let total = self.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let seconds = self.bar.elapsed().as_secs_f64().max(0.001);
+        self.bar.set_message(format!("{}/s", format_size((total as f64 / seconds) as u64)));
+        self.bar.inc(1);
+    }
+    
+    /// Hide the bar while `f` prints
+    pub fn suspend<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        self.bar.suspend(f)
+    }
+    
+    pub fn finish_and_clear(&self) {
+        self.bar.finish_and_clear();
+    }
+}
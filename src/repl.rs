@@ -0,0 +1,184 @@
+//! `--interactive`: a prompt for browsing the PAKs. They are opened and their
+//! index read once, so each command answers without re-parsing anything.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+
+use crate::asset_path::{normalize_asset_path, normalize_slashes};
+use crate::error::ExtractError;
+use crate::index::EntryInfo;
+use crate::lock::OutputLock;
+use crate::pak_set::PakSet;
+use crate::search::PathMatcher;
+use crate::tree::format_size;
+use crate::{extract_entry, say};
+
+const HELP: &str = "\
+Commands:
+  ls [prefix]      folders and files directly under a folder
+  search <pattern> assets containing the pattern, or matching it as a glob
+  get <path>       extract an entry (or an asset's .uasset) into the output directory
+  info <path>      size, compression, encryption and location of an entry
+  help             show this list
+  quit             leave (also exit or Ctrl-D)";
+
+pub fn run(paks: &mut PakSet, entries: &HashMap<String, EntryInfo>, out_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut files = paks.files();
+    files.sort();
+    say!("{} entries; type help for commands", files.len());
+    
+    let mut editor = DefaultEditor::new()?;
+    loop {
+        let line = match editor.readline("pak> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+        match command {
+            "ls" => list(&files, arg),
+            "search" if !arg.is_empty() => search(&files, arg),
+            "get" if !arg.is_empty() => {
+                if let Err(e) = get(paks, entries, arg, out_dir) {
+                    log::error!("{}", e);
+                }
+            }
+            "info" if !arg.is_empty() => info(paks, entries, arg),
+            "search" | "get" | "info" => say!("Usage: {} <{}>", command, if command == "search" { "pattern" } else { "path" }),
+            "help" | "?" => say!("{}", HELP),
+            "quit" | "exit" => break,
+            _ => say!("Unknown command '{}'; type help for commands", command),
+        }
+    }
+    Ok(())
+}
+
+/// The internal path `arg` names: an entry, or else the asset's `.uasset`
+fn entry_path(entries: &HashMap<String, EntryInfo>, arg: &str) -> Option<String> {
+    let path = normalize_slashes(arg);
+    if entries.contains_key(&path) {
+        return Some(path);
+    }
+    let uasset = normalize_asset_path(&path).uasset();
+    entries.contains_key(&uasset).then_some(uasset)
+}
+
+/// Immediate children of `prefix`: folders (ending in `/`) with their file
+/// counts, then files, each in path order
+fn children<'a>(files: &'a [String], prefix: &str) -> Vec<(&'a str, usize)> {
+    let prefix = normalize_slashes(prefix);
+    let prefix = prefix.trim_matches('/');
+    let mut children: BTreeMap<(bool, &str), usize> = BTreeMap::new();
+    for file in files {
+        let rest = if prefix.is_empty() {
+            file.as_str()
+        } else {
+            match file.strip_prefix(prefix).and_then(|rest| rest.strip_prefix('/')) {
+                Some(rest) => rest,
+                None => continue,
+            }
+        };
+        let child = match rest.find('/') {
+            Some(end) => (false, &rest[..=end]),
+            None => (true, rest),
+        };
+        *children.entry(child).or_default() += 1;
+    }
+    children.into_iter().map(|((_, name), count)| (name, count)).collect()
+}
+
+fn list(files: &[String], prefix: &str) {
+    let children = children(files, prefix);
+    if children.is_empty() {
+        say!("Nothing under '{}'", prefix);
+        return;
+    }
+    for (name, count) in &children {
+        if name.ends_with('/') {
+            say!("  {} ({} files)", name, count);
+        } else {
+            say!("  {}", name);
+        }
+    }
+}
+
+fn search(files: &[String], pattern: &str) {
+    let matcher = match PathMatcher::new(pattern, false) {
+        Ok(matcher) => matcher,
+        Err(e) => {
+            say!("Invalid glob '{}': {}", pattern, e);
+            return;
+        }
+    };
+    let mut count = 0;
+    for path in files.iter().filter(|path| path.ends_with(".uasset") && matcher.is_match(path)) {
+        say!("  {}", normalize_asset_path(path));
+        count += 1;
+    }
+    say!("{} matching assets", count);
+}
+
+fn get(paks: &mut PakSet, entries: &HashMap<String, EntryInfo>, arg: &str, out_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let path = entry_path(entries, arg).ok_or_else(|| ExtractError::AssetNotFound(arg.to_string()))?;
+    let (pak, file, _) = paks.resolve(&path).ok_or_else(|| ExtractError::AssetNotFound(path.clone()))?;
+    // Locked per get rather than for the session, so batch runs can use the directory in between
+    fs::create_dir_all(out_dir)?;
+    let _lock = OutputLock::acquire(out_dir, false)?;
+    let out_path = extract_entry(pak, file, &path, out_dir)
+        .map_err(|e| e.with_entry(entries.get(&path)))?;
+    say!("Saved: {}", out_path.display());
+    Ok(())
+}
+
+fn info(paks: &mut PakSet, entries: &HashMap<String, EntryInfo>, arg: &str) {
+    let Some(entry) = entry_path(entries, arg).and_then(|path| entries.get(&path)) else {
+        say!("No entry '{}'", arg);
+        return;
+    };
+    say!("=== {} ===", entry.path);
+    if let Some((_, _, source)) = paks.resolve(&entry.path) {
+        say!("  PAK:           {}", source.display());
+    }
+    say!("  Size:          {} ({} bytes)", format_size(entry.uncompressed_size), entry.uncompressed_size);
+    say!("  Stored:        {} ({} bytes)", format_size(entry.stored_size()), entry.stored_size());
+    say!("  Storage:       {}", entry.storage());
+    say!("  Data offset:   0x{:X}", entry.data_offset);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn children_lists_folders_then_files_one_level_down() {
+        let files: Vec<String> = [
+            "MotorTown/Content/DataAsset/Cargos.uasset",
+            "MotorTown/Content/DataAsset/Cargos.uexp",
+            "MotorTown/Content/Vehicles/Truck/Truck.uasset",
+            "MotorTown/Content/Vehicles/Bus.uasset",
+            "MotorTown/Content/Readme.txt",
+        ].map(String::from).to_vec();
+        
+        assert_eq!(children(&files, ""), vec![("MotorTown/", 5)]);
+        assert_eq!(
+            children(&files, "MotorTown\\Content/"),
+            vec![("DataAsset/", 2), ("Vehicles/", 2), ("Readme.txt", 1)],
+        );
+        assert_eq!(children(&files, "MotorTown/Content/Vehicles"), vec![("Truck/", 1), ("Bus.uasset", 1)]);
+        assert!(children(&files, "MotorTown/Cont").is_empty());
+    }
+}
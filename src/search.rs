@@ -0,0 +1,69 @@
+//! Matching internal paths for `search`.
+
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+
+pub enum PathMatcher {
+    /// Substring, the default; `pattern` is lowercased unless `case_sensitive`
+    Substring { pattern: String, case_sensitive: bool },
+    /// Glob where `*` stays within a folder and `**` crosses folders
+    Glob(GlobMatcher),
+    /// Regular expression over the full internal path, extension included
+    Regex(Regex),
+}
+
+impl PathMatcher {
+    /// Match as a glob if the pattern contains glob metacharacters, otherwise as a substring
+    pub fn new(pattern: &str, case_sensitive: bool) -> Result<Self, globset::Error> {
+        if pattern.contains(['*', '?', '[', '{']) {
+            Self::glob(pattern, case_sensitive)
+        } else if case_sensitive {
+            Ok(PathMatcher::Substring { pattern: pattern.to_string(), case_sensitive })
+        } else {
+            Ok(PathMatcher::Substring { pattern: pattern.to_lowercase(), case_sensitive })
+        }
+    }
+    
+    pub fn glob(pattern: &str, case_sensitive: bool) -> Result<Self, globset::Error> {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .literal_separator(true)
+            .build()?;
+        Ok(PathMatcher::Glob(glob.compile_matcher()))
+    }
+    
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(PathMatcher::Regex(Regex::new(pattern)?))
+    }
+    
+    /// Whether an internal path matches; globs are matched against the path without `.uasset`
+    pub fn is_match(&self, path: &str) -> bool {
+        match self {
+            PathMatcher::Substring { pattern, case_sensitive: true } => path.contains(pattern.as_str()),
+            PathMatcher::Substring { pattern, case_sensitive: false } => path.to_lowercase().contains(pattern.as_str()),
+            PathMatcher::Glob(glob) => glob.is_match(path.trim_end_matches(".uasset")),
+            PathMatcher::Regex(regex) => regex.is_match(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const PATH: &str = "MotorTown/Content/Vehicles/Vehicle_Truck.uasset";
+    
+    #[test]
+    fn substring_ignores_case_by_default() {
+        assert!(PathMatcher::new("vehicle_truck", false).unwrap().is_match(PATH));
+        assert!(!PathMatcher::new("vehicle_truck", true).unwrap().is_match(PATH));
+        assert!(PathMatcher::new("Vehicle_Truck", true).unwrap().is_match(PATH));
+    }
+    
+    #[test]
+    fn glob_ignores_case_by_default() {
+        assert!(PathMatcher::new("**/vehicle_*", false).unwrap().is_match(PATH));
+        assert!(!PathMatcher::new("**/vehicle_*", true).unwrap().is_match(PATH));
+        assert!(PathMatcher::new("**/Vehicle_*", true).unwrap().is_match(PATH));
+    }
+}
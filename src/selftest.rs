@@ -0,0 +1,107 @@
+//! `selftest`: extract a sample of assets, repack them into a scratch PAK and read
+//! them back, checking that the whole pipeline returns the bytes it started with.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use aes::Aes256;
+use log::{error, info, warn};
+use repak::{Compression, PakBuilder};
+
+use crate::index;
+use crate::say;
+
+pub fn selftest(pak_path: &Path, key: &Aes256, sample: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let (pak, mut reader) = crate::open_pak(pak_path, key)?;
+    let pak_index = index::read_index(&mut reader, Some(key))?;
+    
+    let assets = sample_assets(&pak.files(), sample);
+    if assets.is_empty() {
+        return Err(format!("{} has no .uasset entries to test", pak_path.display()).into());
+    }
+    
+    // Removed when dropped, so a failed run cleans up too
+    let scratch = tempfile::tempdir()?;
+    let extract_dir = scratch.path().join("extracted");
+    
+    info!("Extracting {} assets to {}", assets.len(), extract_dir.display());
+    let mut extracted = Vec::new();
+    for asset in &assets {
+        for ext in [".uasset", ".uexp"] {
+            let entry_path = format!("{}{}", asset, ext);
+            if let Some(entry) = pak_index.find(&entry_path) {
+                let out_path = crate::extract_entry(&pak, &mut reader, &entry_path, &extract_dir)
+                    .map_err(|e| e.with_entry(Some(entry)))?;
+                extracted.push((entry, out_path));
+            }
+        }
+    }
+    
+    let repacked_path = scratch.path().join("selftest.pak");
+    info!("Repacking {} entries into {}", extracted.len(), repacked_path.display());
+    let compression = common_compression(extracted.iter().map(|(entry, _)| *entry))?;
+    let mut writer = PakBuilder::new()
+        .compression(compression)
+        .writer(BufWriter::new(File::create(&repacked_path)?), pak.version(), pak.mount_point().to_string(), pak.path_hash_seed());
+    for (entry, out_path) in &extracted {
+        writer.write_file(&entry.path, entry.compression.is_some(), fs::read(out_path)?)?;
+    }
+    writer.write_index()?.flush()?;
+    
+    info!("Reading back {}", repacked_path.display());
+    let (repacked, mut repacked_reader) = crate::open_pak(&repacked_path, key)?;
+    let mut mismatches = 0;
+    for (entry, out_path) in &extracted {
+        let original = fs::read(out_path)?;
+        match repacked.get(&entry.path, &mut repacked_reader) {
+            Ok(data) if data == original => say!("  {} ... OK ({} bytes)", entry.path, data.len()),
+            Ok(data) => {
+                error!("{}: {} bytes extracted, {} read back", entry.path, original.len(), data.len());
+                mismatches += 1;
+            }
+            Err(e) => {
+                error!("{}: {}", entry.path, e);
+                mismatches += 1;
+            }
+        }
+    }
+    
+    scratch.close()?;
+    
+    if mismatches > 0 {
+        return Err(format!("Self-test failed: {} of {} entries did not round-trip", mismatches, extracted.len()).into());
+    }
+    say!("\n=== Self-test passed: {} entries from {} assets round-tripped ===", extracted.len(), assets.len());
+    Ok(())
+}
+
+
+/// The writer applies one algorithm to every compressed entry, so use the most common one;
+/// only the round trip is checked, so recompressing the others is harmless
+fn common_compression<'a>(entries: impl IntoIterator<Item = &'a index::EntryInfo>) -> Result<Option<Compression>, String> {
+    let mut method_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for entry in entries {
+        if let Some(method) = &entry.compression {
+            *method_counts.entry(method.as_str()).or_default() += 1;
+        }
+    }
+    let compression = method_counts.iter()
+        .max_by_key(|(_, count)| **count)
+        .map(|(method, _)| crate::parse_compression(method))
+        .transpose()?;
+    if method_counts.len() > 1 {
+        warn!("PAKs mix compression methods {:?}; all compressed entries will use {:?}",
+            method_counts.keys().collect::<Vec<_>>(), compression);
+    }
+    Ok(compression)
+}
+
+/// Up to `count` assets (paths without `.uasset`) spread evenly over the PAK
+fn sample_assets(files: &[String], count: usize) -> Vec<String> {
+    let mut assets: Vec<&str> = files.iter().filter_map(|f| f.strip_suffix(".uasset")).collect();
+    assets.sort_unstable();
+    let step = assets.len().div_ceil(count.max(1)).max(1);
+    assets.into_iter().step_by(step).take(count).map(String::from).collect()
+}
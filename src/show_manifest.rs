@@ -0,0 +1,115 @@
+//! `show-manifest`: pretty-print a manifest written by a previous extraction.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::say;
+use crate::tree::format_size;
+
+/// Lenient view of manifest.json: fields added in later versions are optional
+#[derive(Deserialize)]
+struct ManifestView {
+    #[serde(default = "first_version")]
+    version: u32,
+    #[serde(default)]
+    extracted: Vec<AssetView>,
+    #[serde(default)]
+    failed: Vec<FailedView>,
+    #[serde(default)]
+    collisions: Vec<CollisionView>,
+    #[serde(default)]
+    verification_failed: Vec<serde_json::Value>,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+struct AssetView {
+    name: String,
+    pak_path: String,
+    uasset: String,
+    uexp: Option<String>,
+    uasset_size: Option<u64>,
+    uexp_size: Option<u64>,
+    sha256: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CollisionView {
+    asset: String,
+    conflicts_with: String,
+    written_as: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FailedView {
+    #[serde(alias = "pak_path")]
+    asset: String,
+    error: String,
+}
+
+pub fn show_manifest(manifest_path: &Path, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(manifest_path)?;
+    if json {
+        say!("{}", content);
+        return Ok(());
+    }
+    
+    let manifest: ManifestView = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid manifest {}: {}", manifest_path.display(), e))?;
+    crate::check_manifest_version(manifest_path, manifest.version)?;
+    let out_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    
+    // Older manifests have no sizes, so fall back to the files on disk
+    let file_size = |recorded: Option<u64>, file: &str| {
+        recorded.or_else(|| fs::metadata(out_dir.join(file)).ok().map(|m| m.len()))
+    };
+    
+    let name_width = manifest.extracted.iter().map(|a| a.name.len()).max().unwrap_or(4).max(4);
+    let path_width = manifest.extracted.iter().map(|a| a.pak_path.len()).max().unwrap_or(8).max(8);
+    
+    say!("{:<name_width$}  {:<path_width$}  {:>10}  {:>10}  SHA-256", "NAME", "PAK PATH", "UASSET", "UEXP");
+    
+    let mut total = 0;
+    for asset in &manifest.extracted {
+        let uasset_size = file_size(asset.uasset_size, &asset.uasset);
+        let uexp_size = asset.uexp.as_deref().and_then(|uexp| file_size(asset.uexp_size, uexp));
+        total += uasset_size.unwrap_or(0) + uexp_size.unwrap_or(0);
+        
+        say!(
+            "{:<name_width$}  {:<path_width$}  {:>10}  {:>10}  {}",
+            asset.name,
+            asset.pak_path,
+            uasset_size.map(format_size).unwrap_or_else(|| "?".to_string()),
+            uexp_size.map(format_size).unwrap_or_else(|| "-".to_string()),
+            asset.sha256.as_deref().filter(|h| !h.is_empty()).map(|h| &h[..h.len().min(12)]).unwrap_or("-"),
+        );
+    }
+    
+    say!("\nTotal: {} assets, {}", manifest.extracted.len(), format_size(total));
+    
+    if !manifest.failed.is_empty() {
+        say!("\nFailed: {} assets", manifest.failed.len());
+        for failed in &manifest.failed {
+            say!("  {}: {}", failed.asset, failed.error);
+        }
+    }
+    if !manifest.collisions.is_empty() {
+        say!("\nFile name collisions: {} assets", manifest.collisions.len());
+        for collision in &manifest.collisions {
+            match &collision.written_as {
+                Some(written_as) => say!("  {} (same name as {}): written as {}", collision.asset, collision.conflicts_with, written_as),
+                None => say!("  {} (same name as {}): skipped", collision.asset, collision.conflicts_with),
+            }
+        }
+    }
+    if !manifest.verification_failed.is_empty() {
+        say!("Verification failed: {} entries", manifest.verification_failed.len());
+    }
+    
+    Ok(())
+}
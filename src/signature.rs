@@ -0,0 +1,134 @@
+//! `.sig` files, which games with PAK signing enabled expect next to each PAK. UnrealPak
+//! writes a CRC32 of every 64 KiB chunk of the PAK, followed by an RSA-encrypted hash of
+//! those CRCs made with the game's private key. repak has no signing support and modders
+//! don't have that key, so the signatures written here carry the chunk hashes with an
+//! empty encrypted hash: enough for tools and games that only check chunks.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+
+const SIG_MAGIC: u32 = 0x73832DAA;
+const SIG_VERSION: u32 = 1;
+/// Bytes of PAK covered by each chunk hash
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+pub struct Signature {
+    /// RSA-encrypted hash of the chunk hashes; empty in the signatures we write
+    pub encrypted_hash: Vec<u8>,
+    /// CRC32 of each chunk of the PAK, the last one possibly shorter
+    pub chunk_hashes: Vec<u32>,
+}
+
+/// `<name>.sig` next to `<name>.pak`
+pub fn sig_path(pak: &Path) -> PathBuf {
+    pak.with_extension("sig")
+}
+
+impl Signature {
+    /// Hash the chunks of a PAK, leaving the encrypted hash empty
+    pub fn of_pak(mut pak: impl Read) -> io::Result<Self> {
+        let mut chunk_hashes = Vec::new();
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            let len = read_chunk(&mut pak, &mut chunk)?;
+            if len == 0 {
+                break;
+            }
+            let mut crc = flate2::Crc::new();
+            crc.update(&chunk[..len]);
+            chunk_hashes.push(crc.sum());
+        }
+        Ok(Signature { encrypted_hash: Vec::new(), chunk_hashes })
+    }
+    
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        if reader.read_u32::<LE>()? != SIG_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a PAK signature file (bad magic)"));
+        }
+        let version = reader.read_u32::<LE>()?;
+        if version != SIG_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported signature version {}", version)));
+        }
+        let mut encrypted_hash = vec![0; read_len(&mut reader)?];
+        reader.read_exact(&mut encrypted_hash)?;
+        let chunk_hashes = (0..read_len(&mut reader)?)
+            .map(|_| reader.read_u32::<LE>())
+            .collect::<io::Result<_>>()?;
+        Ok(Signature { encrypted_hash, chunk_hashes })
+    }
+    
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_u32::<LE>(SIG_MAGIC)?;
+        writer.write_u32::<LE>(SIG_VERSION)?;
+        writer.write_i32::<LE>(self.encrypted_hash.len() as i32)?;
+        writer.write_all(&self.encrypted_hash)?;
+        writer.write_i32::<LE>(self.chunk_hashes.len() as i32)?;
+        for hash in &self.chunk_hashes {
+            writer.write_u32::<LE>(*hash)?;
+        }
+        Ok(())
+    }
+    
+    /// Write `<pak>.sig` for the PAK at `pak`, returning its path
+    pub fn write_for(pak: &Path) -> io::Result<PathBuf> {
+        let signature = Signature::of_pak(BufReader::new(File::open(pak)?))?;
+        let path = sig_path(pak);
+        let mut out = BufWriter::new(File::create(&path)?);
+        signature.write(&mut out)?;
+        out.flush()?;
+        Ok(path)
+    }
+    
+    /// Indices of the chunks whose hash differs from `actual`'s, including chunks only
+    /// one of the two has
+    pub fn mismatched_chunks(&self, actual: &Signature) -> Vec<usize> {
+        let chunks = self.chunk_hashes.len().max(actual.chunk_hashes.len());
+        (0..chunks)
+            .filter(|&i| self.chunk_hashes.get(i) != actual.chunk_hashes.get(i))
+            .collect()
+    }
+}
+
+/// Fill `chunk` as far as the reader allows, returning how much was read
+fn read_chunk(reader: &mut impl Read, chunk: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < chunk.len() {
+        match reader.read(&mut chunk[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
+
+/// An array length, which UE stores as a signed 32-bit count
+fn read_len(reader: &mut impl Read) -> io::Result<usize> {
+    let len = reader.read_i32::<LE>()?;
+    usize::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("negative array length {}", len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn signature_round_trips_and_finds_changed_chunks() {
+        let mut pak = vec![7u8; CHUNK_SIZE * 2 + 100];
+        let signature = Signature::of_pak(pak.as_slice()).unwrap();
+        assert_eq!(signature.chunk_hashes.len(), 3);
+        
+        let mut sig_file = Vec::new();
+        signature.write(&mut sig_file).unwrap();
+        let read_back = Signature::read(sig_file.as_slice()).unwrap();
+        assert!(read_back.encrypted_hash.is_empty());
+        assert_eq!(read_back.chunk_hashes, signature.chunk_hashes);
+        
+        pak[CHUNK_SIZE + 1] = 8;
+        pak.push(0);
+        let changed = Signature::of_pak(pak.as_slice()).unwrap();
+        assert_eq!(read_back.mismatched_chunks(&changed), vec![1, 2]);
+    }
+}
@@ -0,0 +1,112 @@
+//! Split a PAK into several smaller PAKs under a size limit.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use aes::Aes256;
+use log::{info, warn};
+use repak::{Compression, PakBuilder, PakReader};
+
+use crate::index;
+use crate::say;
+
+/// Sibling extensions that belong to the same asset and must stay in one part
+const ASSET_EXTENSIONS: [&str; 4] = [".uasset", ".uexp", ".ubulk", ".uptnl"];
+
+pub fn split_pak(pak_path: &Path, max_size: u64, key: &Aes256) -> Result<(), Box<dyn std::error::Error>> {
+    let (pak, mut file) = crate::open_pak(pak_path, key)?;
+    let pak_index = index::read_index(&mut file, Some(key))?;
+    
+    // Group entries so that an asset's .uasset/.uexp/.ubulk/.uptnl land in the same part
+    let mut groups: BTreeMap<String, Vec<&index::EntryInfo>> = BTreeMap::new();
+    for entry in &pak_index.entries {
+        let stem = ASSET_EXTENSIONS.iter()
+            .find_map(|ext| entry.path.strip_suffix(ext))
+            .unwrap_or(&entry.path);
+        groups.entry(stem.to_string()).or_default().push(entry);
+    }
+    
+    // repak's writer compresses every compressed entry of a PAK with the same method,
+    // so each part only takes assets compressed with one method (or not at all)
+    let mut by_method: BTreeMap<Option<&str>, VecDeque<(&str, Vec<&index::EntryInfo>)>> = BTreeMap::new();
+    for (stem, entries) in &groups {
+        let methods: BTreeSet<&str> = entries.iter().filter_map(|e| e.compression.as_deref()).collect();
+        if methods.len() > 1 {
+            return Err(format!("{} mixes compression methods {:?} across its files, so its part can't keep them", stem, methods).into());
+        }
+        by_method.entry(methods.into_iter().next()).or_default().push_back((stem, entries.clone()));
+    }
+    
+    // Parts of an encrypted PAK are encrypted with the same key
+    let encrypted = pak.encrypted_index() || pak_index.entries.iter().any(|e| e.encrypted);
+    let part_key = encrypted.then_some(key);
+    
+    let stem = pak_path.file_stem().and_then(|s| s.to_str()).unwrap_or("split");
+    let out_dir = pak_path.parent().unwrap_or(Path::new("."));
+    
+    info!("Splitting {} entries into parts of at most {} bytes", pak_index.entries.len(), max_size);
+    
+    let mut part_count = 0;
+    for (method, mut assets) in by_method {
+        let compression = method.map(crate::parse_compression).transpose()?;
+        while !assets.is_empty() {
+            // Greedily take assets in path order, using stored (compressed) size as the estimate
+            let mut take = 0;
+            let mut estimate = 0;
+            for (_, entries) in &assets {
+                let group_size: u64 = entries.iter().map(|e| e.stored_size()).sum();
+                if take > 0 && estimate + group_size > max_size {
+                    break;
+                }
+                take += 1;
+                estimate += group_size;
+            }
+            
+            part_count += 1;
+            let part_path = out_dir.join(format!("{}_{:03}.pak", stem, part_count));
+            let size = loop {
+                let entries: Vec<&index::EntryInfo> = assets.iter().take(take).flat_map(|(_, entries)| entries.iter().copied()).collect();
+                write_part(&pak, &mut file, &part_path, &entries, compression, part_key)?;
+                let size = fs::metadata(&part_path)?.len();
+                if size <= max_size || take == 1 {
+                    break size;
+                }
+                // Recompressing came out larger than the stored sizes; leave the last asset for the next part
+                info!("{} came out at {} bytes; moving {} to the next part", part_path.display(), size, assets[take - 1].0);
+                take -= 1;
+            };
+            if size > max_size {
+                warn!("{} is {} bytes, over --max-size: {} doesn't fit in a part on its own", part_path.display(), size, assets[0].0);
+            }
+            let entry_count: usize = assets.drain(..take).map(|(_, entries)| entries.len()).sum();
+            say!("  {}: {} entries, {} bytes", part_path.display(), entry_count, size);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Write `entries` of `pak` into a new PAK at `part_path`, with the source's version and mount point
+fn write_part(
+    pak: &PakReader,
+    file: &mut BufReader<File>,
+    part_path: &Path,
+    entries: &[&index::EntryInfo],
+    compression: Option<Compression>,
+    key: Option<&Aes256>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = PakBuilder::new().compression(compression);
+    if let Some(key) = key {
+        builder = builder.key(key.clone());
+    }
+    let mut writer = builder
+        .writer(BufWriter::new(File::create(part_path)?), pak.version(), pak.mount_point().to_string(), pak.path_hash_seed());
+    for entry in entries {
+        let data = pak.get(&entry.path, file)?;
+        writer.write_file(&entry.path, entry.compression.is_some(), data)?;
+    }
+    writer.write_index()?.flush()?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! `stats`: where the bulk of a PAK's content lives.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::index::EntryInfo;
+use crate::say;
+use crate::tree::format_size;
+
+/// Entries and uncompressed bytes in one group
+#[derive(Default)]
+struct Group {
+    files: usize,
+    size: u64,
+}
+
+pub fn print_stats<'a>(entries: impl IntoIterator<Item = &'a EntryInfo>) {
+    let mut entries: Vec<&EntryInfo> = entries.into_iter().collect();
+    let mut by_folder: BTreeMap<&str, Group> = BTreeMap::new();
+    let mut by_extension: BTreeMap<String, Group> = BTreeMap::new();
+    for entry in &entries {
+        let folder = match entry.path.split_once('/') {
+            Some((folder, _)) => folder,
+            None => "(root)",
+        };
+        let extension = Path::new(&entry.path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| format!(".{}", e))
+            .unwrap_or_else(|| "(none)".to_string());
+        for group in [by_folder.entry(folder).or_default(), by_extension.entry(extension).or_default()] {
+            group.files += 1;
+            group.size += entry.uncompressed_size;
+        }
+    }
+    
+    say!("=== By top-level folder ===");
+    print_groups(by_folder.iter().map(|(name, group)| (*name, group)));
+    say!("\n=== By extension ===");
+    print_groups(by_extension.iter().map(|(name, group)| (name.as_str(), group)));
+    
+    entries.sort_by_key(|e| Reverse(e.uncompressed_size));
+    say!("\n=== Largest files ===");
+    for entry in entries.iter().take(10) {
+        say!("  {:>10}  {}", format_size(entry.uncompressed_size), entry.path);
+    }
+    
+    let total: u64 = entries.iter().map(|e| e.uncompressed_size).sum();
+    say!("\nTotal: {} files, {}", entries.len(), format_size(total));
+}
+
+/// Groups sorted by size, largest first
+fn print_groups<'a>(groups: impl Iterator<Item = (&'a str, &'a Group)>) {
+    let mut groups: Vec<_> = groups.collect();
+    groups.sort_by_key(|(_, group)| Reverse(group.size));
+    let name_width = groups.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, group) in groups {
+        say!("  {:<name_width$}  {:>7} files  {:>10}", name, group.files, format_size(group.size));
+    }
+}
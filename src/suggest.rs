@@ -0,0 +1,107 @@
+//! "Did you mean ...?" suggestions for mistyped asset paths.
+
+use crate::say;
+
+/// Up to `count` asset paths (without `.uasset`) from `files` closest to `target`
+/// by case-insensitive Levenshtein distance.
+pub fn closest_assets(target: &str, files: &[String], count: usize) -> Vec<String> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let target = target.to_lowercase();
+    let target_len = target.chars().count();
+    let mut best: Vec<(usize, &str)> = Vec::new();
+    
+    for path in files {
+        let Some(asset) = path.strip_suffix(".uasset") else { continue };
+        let lowered = asset.to_lowercase();
+        
+        // The distance is at least the difference in characters, so skip hopeless candidates early
+        let worst = if best.len() == count { best[count - 1].0 } else { usize::MAX };
+        if lowered.chars().count().abs_diff(target_len) >= worst {
+            continue;
+        }
+        
+        let distance = levenshtein(&target, &lowered);
+        if distance < worst {
+            let pos = best.partition_point(|(d, _)| *d <= distance);
+            best.insert(pos, (distance, asset));
+            best.truncate(count);
+        }
+    }
+    
+    best.into_iter().map(|(_, asset)| asset.to_string()).collect()
+}
+
+/// Print suggestions for `target`, if there are any
+pub fn print_suggestions(target: &str, files: &[String]) {
+    let matches = closest_assets(target, files, 3);
+    if !matches.is_empty() {
+        say!("    Did you mean:");
+        for m in matches {
+            say!("      {}", m);
+        }
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(prev + cost);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+    
+    #[test]
+    fn closest_assets_come_first() {
+        let files = files(&[
+            "Content/DataAsset/Vehicles.uasset",
+            "Content/DataAsset/Cargos.uexp",
+            "Content/DataAsset/Cargos.uasset",
+            "Content/DataAsset/Cargo.uasset",
+            "Content/DataAsset/Carts.uasset",
+        ]);
+        assert_eq!(
+            closest_assets("Content/DataAsset/Cargoz", &files, 3),
+            ["Content/DataAsset/Cargos", "Content/DataAsset/Cargo", "Content/DataAsset/Carts"],
+        );
+    }
+    
+    #[test]
+    fn case_is_ignored() {
+        let files = files(&["Content/DataAsset/Cargos.uasset", "Content/Maps/Town.uasset"]);
+        assert_eq!(closest_assets("content/dataasset/CARGOS", &files, 1), ["Content/DataAsset/Cargos"]);
+    }
+    
+    #[test]
+    fn zero_suggestions_asked_for() {
+        let files = files(&["Content/DataAsset/Cargos.uasset"]);
+        assert!(closest_assets("Content/DataAsset/Cargos", &files, 0).is_empty());
+    }
+    
+    #[test]
+    fn non_ascii_lengths_count_characters() {
+        // "Grße" is one character from "Größe" but two bytes shorter, so comparing byte
+        // lengths would skip it once "Grxxe" (two characters off) is the best so far
+        let files = files(&["Content/Grxxe.uasset", "Content/Grße.uasset"]);
+        assert_eq!(closest_assets("Content/Größe", &files, 1), ["Content/Grße"]);
+    }
+}
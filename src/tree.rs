@@ -0,0 +1,88 @@
+//! Directory tree built from slash-delimited PAK paths.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::say;
+
+#[derive(Default)]
+pub struct TreeNode {
+    pub children: BTreeMap<String, TreeNode>,
+    /// Total size of this file, or of every file below this folder
+    pub size: u64,
+    /// Number of files below this folder (1 for a file)
+    pub files: usize,
+}
+
+impl TreeNode {
+    pub fn insert(&mut self, path: &str, size: u64) {
+        let mut node = self;
+        node.size += size;
+        node.files += 1;
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            node = node.children.entry(part.to_string()).or_default();
+            node.size += size;
+            node.files += 1;
+        }
+    }
+    
+    pub fn is_file(&self) -> bool {
+        self.children.is_empty()
+    }
+    
+    /// Print the children of this node like `tree(1)`, folders with subtotals,
+    /// going at most `depth` folders deep
+    pub fn print(&self, prefix: &str, depth: Option<usize>) {
+        let count = self.children.len();
+        for (i, (name, child)) in self.children.iter().enumerate() {
+            let last = i + 1 == count;
+            let branch = if last { "└── " } else { "├── " };
+            if child.is_file() {
+                say!("{}{}{} ({})", prefix, branch, name, format_size(child.size));
+            } else {
+                say!("{}{}{}/ ({} files, {})", prefix, branch, name, child.files, format_size(child.size));
+                if depth != Some(1) {
+                    let indent = if last { "    " } else { "│   " };
+                    child.print(&format!("{}{}", prefix, indent), depth.map(|d| d.saturating_sub(1)));
+                }
+            }
+        }
+    }
+}
+
+/// Nested JSON form of a tree, cut off at a depth
+#[derive(Serialize)]
+pub struct JsonTree<'a> {
+    pub size: u64,
+    pub files: usize,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub children: BTreeMap<&'a str, JsonTree<'a>>,
+}
+
+impl TreeNode {
+    pub fn to_json(&self, depth: Option<usize>) -> JsonTree<'_> {
+        let children = if depth == Some(0) {
+            BTreeMap::new()
+        } else {
+            self.children.iter()
+                .map(|(name, child)| (name.as_str(), child.to_json(depth.map(|d| d - 1))))
+                .collect()
+        };
+        JsonTree { size: self.size, files: self.files, children }
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -0,0 +1,41 @@
+//! User-wide defaults read from the platform config directory
+//! (`~/.config/mt-pak-extract/config.json` on Linux).
+
+use std::fs;
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+use serde::Deserialize;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct UserConfig {
+    /// Environment variable holding the AES key (defaults to `KEY`)
+    pub key_env: Option<String>,
+    /// AES key used when the key environment variable is not set
+    pub key: Option<String>,
+    /// PAK file to open instead of `MotorTown-WindowsServer.pak`
+    pub pak: Option<String>,
+    /// Output directory for batch extraction instead of `out`
+    pub out_dir: Option<String>,
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "mt-pak-extract").map(|dirs| dirs.config_dir().join("config.json"))
+}
+
+/// Load the user config, or defaults if there is none
+pub fn load() -> Result<UserConfig, Box<dyn std::error::Error>> {
+    let Some(path) = config_path() else {
+        return Ok(UserConfig::default());
+    };
+    if !path.exists() {
+        return Ok(UserConfig::default());
+    }
+    
+    let content = fs::read_to_string(&path)?;
+    let config = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid user config {}: {}", path.display(), e))?;
+    log::info!("Using user config: {}", path.display());
+    Ok(config)
+}
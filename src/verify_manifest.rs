@@ -0,0 +1,55 @@
+//! `verify-manifest`: check the files of a previous extraction against the
+//! SHA-256 checksums recorded in its manifest.
+
+use std::fs;
+use std::path::Path;
+
+use crate::say;
+use crate::{read_manifest, sha256_hex};
+
+pub fn verify_manifest(manifest_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = read_manifest(manifest_path)?;
+    let out_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    
+    log::info!("Verifying {} assets in {}", manifest.extracted.len(), out_dir.display());
+    
+    let mut ok = 0;
+    let mut mismatched = 0;
+    let mut unchecked = 0;
+    for asset in &manifest.extracted {
+        let uasset = (asset.uasset.as_str(), Some(asset.sha256.as_str()).filter(|h| !h.is_empty()));
+        let uexp = asset.uexp.as_deref().map(|uexp| (uexp, asset.uexp_sha256.as_deref()));
+        
+        for (file, expected) in std::iter::once(uasset).chain(uexp) {
+            let Some(expected) = expected else {
+                unchecked += 1;
+                continue;
+            };
+            match fs::read(out_dir.join(file)) {
+                Ok(data) => {
+                    let actual = sha256_hex(&data);
+                    if actual == expected {
+                        ok += 1;
+                    } else {
+                        say!("  MISMATCH: {} (expected {}, got {})", file, expected, actual);
+                        mismatched += 1;
+                    }
+                }
+                Err(e) => {
+                    say!("  MISSING: {}: {}", file, e);
+                    mismatched += 1;
+                }
+            }
+        }
+    }
+    
+    say!("\n=== Checked {} files: {} OK, {} mismatched ===", ok + mismatched, ok, mismatched);
+    if unchecked > 0 {
+        say!("{} files have no checksum (manifest written before version 3)", unchecked);
+    }
+    
+    if mismatched > 0 {
+        return Err(format!("{} files do not match {}", mismatched, manifest_path.display()).into());
+    }
+    Ok(())
+}